@@ -1,4 +1,7 @@
 fn main() {
-    println!("cargo:rustc-link-lib=X11");
-    println!("cargo:rustc-link-search=native=/usr/X11R6/lib");
+    // libX11 is only needed to set the name of the root window.
+    if std::env::var_os("CARGO_FEATURE_X11").is_some() {
+        println!("cargo:rustc-link-lib=X11");
+        println!("cargo:rustc-link-search=native=/usr/X11R6/lib");
+    }
 }
@@ -0,0 +1,171 @@
+//! # Command line arguments
+//!
+//! Options given on the command line take precedence over the configuration
+//! file and the defaults in `config.rs`.
+
+// std imports
+use std::env;
+
+// Lib imports
+use rwmstatus::get_tz_time;
+
+// Internal module imports
+use config::{parse_output, Config, Output};
+
+const USAGE: &str = "\
+usage: rwmstatus [options]
+
+options:
+    --config PATH        configuration file
+    --hwmon-path PATH    directory with the hwmon monitors
+    --batt-path PATH     directory with the batteries
+    --interval SECONDS   time between updates of sections without their own
+                         interval
+    --tz LABEL=ZONE      show the time in ZONE, e.g. A=America/Buenos_Aires,
+                         may be repeated and replaces the configured zones
+    --once               update the status once and exit
+    --output OUTPUT      where to write the status, stdout, i3bar, swaybar,
+                         or x11, may be repeated to write to several outputs,
+                         by default x11 under X11, swaybar under Wayland, and
+                         stdout otherwise
+    --stdout             same as --output stdout
+    --fifo PATH          also write the status to a FIFO or file
+    --self-test          read every device once and report the results
+    --syslog             log to syslog instead of stderr
+    -v, --verbose        also log every repeated failed readout and other
+                         details
+    -h, --help           print this message";
+
+/// Parsed command line arguments.
+pub struct Args {
+    pub config: Option<String>,
+    pub hwmon_path: Option<String>,
+    pub batt_path: Option<String>,
+    pub interval: Option<u64>,
+    pub tzs: Vec<(char, String)>,
+    pub once: bool,
+    pub outputs: Vec<Output>,
+    pub fifo: Option<String>,
+    pub self_test: bool,
+    pub syslog: bool,
+    pub verbose: bool,
+}
+
+impl Args {
+    /// Parse the command line arguments.  Prints a message to stderr and exits
+    /// if they are invalid.
+    pub fn parse() -> Args {
+        match Args::parse_from(env::args().skip(1)) {
+            Ok(args) => args,
+            Err(err) => {
+                eprintln!("rwmstatus: {}\n\n{}", err, USAGE);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Parse the provided arguments, not including the program name.
+    fn parse_from<I: Iterator<Item = String>>(mut iter: I) -> Result<Args, String> {
+        let mut args = Args {
+            config: None,
+            hwmon_path: None,
+            batt_path: None,
+            interval: None,
+            tzs: vec![],
+            once: false,
+            outputs: vec![],
+            fifo: None,
+            self_test: false,
+            syslog: false,
+            verbose: false,
+        };
+
+        while let Some(arg) = iter.next() {
+            // Accept both "--option value" and "--option=value".
+            let (option, inline_value) = match arg.find('=') {
+                Some(idx) if arg.starts_with("--") => {
+                    (String::from(&arg[..idx]), Some(String::from(&arg[idx + 1..])))
+                }
+                _ => (arg, None),
+            };
+
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| iter.next())
+                    .ok_or_else(|| format!("{} requires a value", option))
+            };
+
+            match option.as_str() {
+                "--config" => args.config = Some(value()?),
+                "--hwmon-path" => args.hwmon_path = Some(value()?),
+                "--batt-path" => args.batt_path = Some(value()?),
+                "--interval" => args.interval = Some(parse_interval(&value()?)?),
+                "--tz" => args.tzs.push(parse_tz(&value()?)?),
+                "--once" => args.once = true,
+                "--output" => args.outputs.push(parse_output(&value()?)?),
+                "--stdout" => args.outputs.push(Output::Stdout),
+                "--fifo" => args.fifo = Some(value()?),
+                "--self-test" => args.self_test = true,
+                "--syslog" => args.syslog = true,
+                "-v" | "--verbose" => args.verbose = true,
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                _ => return Err(format!("unknown option {}", option)),
+            }
+        }
+
+        Ok(args)
+    }
+
+    /// Override the configuration with the options given on the command line.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(ref hwmon_path) = self.hwmon_path {
+            config.hwmon_path = hwmon_path.clone();
+        }
+        if let Some(ref batt_path) = self.batt_path {
+            config.batt_path = batt_path.clone();
+        }
+        if let Some(interval) = self.interval {
+            config.interval = interval;
+        }
+        if !self.outputs.is_empty() {
+            config.outputs = self.outputs.clone();
+        }
+        if let Some(ref fifo) = self.fifo {
+            config.fifo = Some(fifo.clone());
+        }
+        if !self.tzs.is_empty() {
+            config.tzs = self
+                .tzs
+                .iter()
+                .map(|(label, name)| (*label, name.clone(), None))
+                .collect();
+        }
+    }
+}
+
+/// Parse the refresh interval which must be a positive number of seconds.
+fn parse_interval(value: &str) -> Result<u64, String> {
+    match value.parse() {
+        Ok(interval) if interval > 0 => Ok(interval),
+        _ => Err(format!("invalid interval {}, must be a positive integer", value)),
+    }
+}
+
+/// Parse a `LABEL=ZONE` time zone, e.g. `A=America/Buenos_Aires`.
+fn parse_tz(value: &str) -> Result<(char, String), String> {
+    let err = || format!("invalid time zone {}, must be LABEL=ZONE", value);
+    let (label, name) = value.split_once('=').ok_or_else(err)?;
+
+    let mut chars = label.chars();
+    let label = match (chars.next(), chars.next()) {
+        (Some(label), None) => label,
+        _ => return Err(format!("invalid time zone label {}, must be a single character", label)),
+    };
+
+    get_tz_time(name, "").map_err(|err| format!("invalid time zone {}: {}", name, err))?;
+    Ok((label, String::from(name)))
+}
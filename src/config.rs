@@ -1,4 +1,973 @@
 //! # rwmstatus configuration
+//!
+//! The constants are the defaults which may be overridden at runtime by the
+//! configuration file, `~/.config/rwmstatus/config.toml` unless another path
+//! is given with `--config`.
 
-/// Additional time zones to display (short name, full name).
-pub const TZS: [(char, &str); 2] = [('A', "America/Buenos_Aires"), ('U', "UTC")];
+// std imports
+use std::env;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Lib imports
+use rwmstatus::{
+    BattBackend, BattFormat, BattMode, CommandMonitor, FreqMode, Limit, Markup, RwmStatus,
+    Section, StatusFormat, TempUnit,
+};
+
+// Internal module imports
+use toml::{self, Table, Value};
+
+/// Directory with the hwmon temperature monitors.
+#[cfg(target_os = "linux")]
+pub const HWMON_PATH: &str = "/sys/devices/virtual/hwmon";
+
+/// The sysctl node with the ACPI thermal zones.
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+pub const HWMON_PATH: &str = "hw.acpi.thermal";
+
+/// The sysctl node with the sensor devices.
+#[cfg(target_os = "openbsd")]
+pub const HWMON_PATH: &str = "hw.sensors";
+
+/// Directory with the batteries.
+#[cfg(target_os = "linux")]
+pub const BATT_PATH: &str = "/sys/class/power_supply";
+
+/// The sysctl node of the combined ACPI batteries.
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+pub const BATT_PATH: &str = "hw.acpi.battery";
+
+/// The APM device with the battery.
+#[cfg(target_os = "openbsd")]
+pub const BATT_PATH: &str = "/dev/apm";
+
+/// Directory with the CPUs.
+pub const CPU_PATH: &str = "/sys/devices/system/cpu";
+
+/// Directory with the backlights.
+pub const BACKLIGHT_PATH: &str = "/sys/class/backlight";
+
+/// Directory with the DRM cards of the GPUs.
+pub const DRM_PATH: &str = "/sys/class/drm";
+
+/// Time between updates of a section in seconds unless it has its own
+/// interval in `INTERVALS`.
+pub const INTERVAL: u64 = 60;
+
+/// Time between updates of the provided sections in seconds, their previous
+/// readouts are reused in between.
+pub const INTERVALS: &[(Section, u64)] = &[
+    (Section::Temperatures, 5),
+    (Section::Gpu, 5),
+    (Section::LoadAvgs, 5),
+    (Section::Cpu, 5),
+    (Section::Ping, 10),
+    (Section::Batteries, 30),
+];
+
+/// Where to write the status, every update is written to each output.  If
+/// empty, the output is chosen based on the session, see `Output::detect`.
+pub const OUTPUTS: &[Output] = &[];
+
+/// FIFO or file to also write each status line to, e.g.
+/// `Some("/tmp/rwmstatus.fifo")`.
+pub const FIFO: Option<&str> = None;
+
+/// Wireless interface to show the ESSID and link quality of, e.g.
+/// `Some("wlan0")`.
+pub const WIFI_IFACE: Option<&str> = None;
+
+/// ALSA simple mixer control to show the volume of, e.g. `Some("Master")`.
+pub const MIXER: Option<&str> = None;
+
+/// Show what the active media player is playing.
+pub const MEDIA: bool = false;
+
+/// Number of characters the media readout is truncated to.
+pub const MEDIA_WIDTH: usize = 40;
+
+/// ICAO code of the weather station to show the weather of, e.g.
+/// `Some("EGLL")`.  Requires the `weather` feature.
+pub const WEATHER_STATION: Option<&str> = None;
+
+/// Time between fetches of the weather in seconds.
+pub const WEATHER_INTERVAL: u64 = 30 * 60;
+
+/// Maildirs to count the unread mails of, e.g. `&["~/Mail/INBOX"]`.  A
+/// leading `~` stands for the home directory.
+pub const MAILDIRS: &[&str] = &[];
+
+/// Network interfaces to show the throughput of, e.g. `&["eth0"]`.
+pub const NET_IFACES: &[&str] = &[];
+
+/// VPN interfaces to show whether they are up, e.g. `&["wg0", "tun*"]`.
+pub const VPN_IFACES: &[&str] = &[];
+
+/// HTTPS endpoint replying with the public IP address to show, e.g.
+/// `Some("https://ifconfig.me/ip")`.
+pub const PUBLIC_IP_URL: Option<&str> = None;
+
+/// Time between fetches of the public IP address in seconds.
+pub const PUBLIC_IP_INTERVAL: u64 = 10 * 60;
+
+/// Host to show the round trip time to, e.g. `Some("1.1.1.1")`.
+pub const PING_HOST: Option<&str> = None;
+
+/// Time to wait for the reply to a ping in seconds.
+pub const PING_TIMEOUT: u64 = 2;
+
+/// Time a command of a `[[command]]` table may run for in seconds before it
+/// is killed.
+pub const COMMAND_TIMEOUT: u64 = 10;
+
+/// Mount points to show the available space of, e.g. `&["/", "/home"]`.
+pub const MOUNTS: &[&str] = &[];
+
+/// Additional time zones to display (short name, full name, `chrono` format).
+/// A format of `None` shows the time as `%H:%M`.
+pub const TZS: [(char, &str, Option<&str>); 2] = [
+    ('A', "America/Buenos_Aires", None),
+    ('U', "UTC", None),
+];
+
+/// Format of the local time shown after the time zones.
+pub const TIME_FORMAT: &str = "KW %W %a %d %b %H:%M %Z %Y";
+
+/// Shown in place of a readout which failed, e.g. because its device is
+/// gone.  The failure is logged.
+pub const PLACEHOLDER: &str = "n/a";
+
+/// Separator between sections of the status line.
+pub const SEPARATOR: &str = " ";
+
+/// Separator between the readouts of different devices in a section.
+pub const DEVICE_SEPARATOR: &str = "|";
+
+/// Sections of the status line, in order, with their prefixes.  The other
+/// sections are only shown once they are listed.
+pub const SECTIONS: [(Section, &str); 4] = [
+    (Section::Temperatures, "T:"),
+    (Section::LoadAvgs, "L:"),
+    (Section::Batteries, "B:"),
+    (Section::Times, ""),
+];
+
+/// Prefixes of the sections listed without one.
+pub const PREFIXES: [(Section, &str); 21] = [
+    (Section::Temperatures, "T:"),
+    (Section::Fans, "FAN:"),
+    (Section::Gpu, "G:"),
+    (Section::LoadAvgs, "L:"),
+    (Section::Cpu, "C:"),
+    (Section::Freq, "F:"),
+    (Section::Memory, "M:"),
+    (Section::Swap, "S:"),
+    (Section::Disks, "D:"),
+    (Section::Batteries, "B:"),
+    (Section::Wifi, "W:"),
+    (Section::Network, "N:"),
+    (Section::Vpn, "VPN:"),
+    (Section::Ping, "ping:"),
+    (Section::Brightness, "☀:"),
+    (Section::Volume, "V:"),
+    (Section::Media, "♫ "),
+    (Section::Mail, "✉:"),
+    (Section::Weather, ""),
+    (Section::Uptime, ""),
+    (Section::Times, ""),
+];
+
+/// Status line template, e.g. `Some("{temp} | {load} | {batt} | {time}")`.
+/// The placeholders `{temp}`, `{fan}`, `{gpu}`, `{load}`, `{cpu}`, `{freq}`,
+/// `{mem}`, `{swap}`, `{disk}`, `{batt}`, `{wifi}`, `{net}`, `{vpn}`,
+/// `{ping}`, `{bright}`, `{vol}`, `{media}`, `{mail}`, `{weather}`,
+/// `{uptime}`, and `{time}` are replaced by the readouts and sub-fields such
+/// as `{batt:percent}` by a single value, see `RwmStatus::get_subfield`.
+/// `None` uses the `SECTIONS` layout.
+pub const TEMPLATE: Option<&str> = None;
+
+/// How the colours of sections beyond a threshold are written into the
+/// status line.  Bars using the i3bar protocol get the colours regardless.
+pub const MARKUP: Markup = Markup::Plain;
+
+/// Colours of sections whose value is beyond a limit (section, limit,
+/// colour), see `RwmStatus::get_value`.  If several limits are crossed the
+/// last one wins.
+pub const THRESHOLDS: &[(Section, Limit, &str)] = &[
+    (Section::Temperatures, Limit::Above(80.0), "#ff8800"),
+    (Section::Batteries, Limit::Below(15.0), "#ff0000"),
+    (Section::Ping, Limit::Above(200.0), "#ff0000"),
+];
+
+/// Alerts shown as desktop notifications once a section is beyond a limit
+/// (section, limit, margin, message), see `RwmStatus::add_alert`.  An alert
+/// is shown again only after the value is back within the limit by the
+/// margin.  Placeholders such as `{batt}` in the message are replaced by the
+/// readouts.  Requires the `notify` feature.
+pub const ALERTS: &[(Section, Limit, f64, &str)] = &[
+    (Section::Batteries, Limit::Below(10.0), 5.0, "Battery low: {batt}"),
+    (Section::Temperatures, Limit::Above(90.0), 5.0, "Temperature high: {temp}"),
+    (Section::Disks, Limit::Below(5.0), 1.0, "Disk nearly full: {disk}"),
+];
+
+/// Margin of the `[[alert]]` tables which do not set one.
+pub const ALERT_MARGIN: f64 = 5.0;
+
+/// Unit in which temperatures are displayed.
+pub const TEMP_UNIT: TempUnit = TempUnit::Celsius;
+
+/// Show the usage of each core after the overall CPU usage.
+pub const CPU_PER_CORE: bool = false;
+
+/// How the frequencies of several CPUs are combined.
+pub const FREQ_MODE: FreqMode = FreqMode::Max;
+
+/// Temperature sensors to show, all of them if empty, e.g.
+/// `&["Package id 0", "nvme:Composite"]`, see `RwmStatus::set_temp_sensors`.
+pub const TEMP_SENSORS: &[&str] = &[];
+
+/// Show whether temperatures are rising or falling.
+pub const TEMP_TREND: bool = false;
+
+/// Battery readout format.
+pub const BATT_FORMAT: BattFormat = BattFormat::Percentage;
+
+/// Show several batteries separately or combined.
+pub const BATT_MODE: BattMode = BattMode::PerBattery;
+
+/// Where the battery readouts come from.
+pub const BATT_BACKEND: BattBackend = BattBackend::Sysfs;
+
+/// Show the health of each battery, its full charge capacity as a
+/// percentage of its design capacity.
+pub const BATT_HEALTH: bool = false;
+
+/// Animate the status of charging batteries.
+pub const CHARGE_ANIMATION: bool = false;
+
+/// Frames of the charging animation.
+pub const CHARGE_FRAMES: [&str; 4] = ["▁", "▃", "▅", "▇"];
+
+/// Address of the JSON HTTP endpoint.
+#[cfg(feature = "http")]
+pub const HTTP_ADDR: &str = "127.0.0.1:9273";
+
+/// ## Output
+///
+/// Where the status is written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Output {
+    /// The name of the X11 root window.
+    X11,
+    /// A line per update on stdout.
+    Stdout,
+    /// The i3bar JSON protocol on stdout, also understood by swaybar.
+    I3bar,
+}
+
+impl Output {
+    /// Return the output for the current session: the X11 root window under
+    /// X11, the swaybar protocol under Wayland, and stdout otherwise.
+    pub fn detect() -> Output {
+        if cfg!(feature = "x11") && env::var_os("DISPLAY").is_some() {
+            Output::X11
+        } else if env::var_os("WAYLAND_DISPLAY").is_some() {
+            Output::I3bar
+        } else {
+            Output::Stdout
+        }
+    }
+}
+
+/// ## Config
+///
+/// Runtime configuration.  Settings missing from the configuration file keep
+/// their default values from the constants above.
+pub struct Config {
+    pub hwmon_path: String,
+    pub temp_sensors: Vec<String>,
+    pub drm_path: String,
+    pub batt_path: String,
+    pub tzs: Vec<(char, String, Option<String>)>,
+    pub time_format: String,
+    pub net_ifaces: Vec<String>,
+    pub wifi_iface: Option<String>,
+    pub vpn_ifaces: Vec<String>,
+    pub public_ip_url: Option<String>,
+    pub public_ip_interval: u64,
+    pub ping_host: Option<String>,
+    pub ping_timeout: u64,
+    pub mixer: Option<String>,
+    pub media: bool,
+    pub media_width: usize,
+    pub maildirs: Vec<String>,
+    pub weather_station: Option<String>,
+    pub weather_interval: u64,
+    pub batt_mode: BattMode,
+    pub batt_health: bool,
+    pub batt_backend: BattBackend,
+    pub mounts: Vec<String>,
+    pub commands: Vec<ShellCommand>,
+    pub format: StatusFormat,
+    pub placeholder: String,
+    pub thresholds: Vec<(Section, Limit, String)>,
+    pub disk_thresholds: Vec<(String, Limit, String)>,
+    pub alerts: Vec<AlertRule>,
+    pub interval: u64,
+    pub intervals: Vec<(Section, u64)>,
+    pub outputs: Vec<Output>,
+    pub fifo: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            hwmon_path: String::from(HWMON_PATH),
+            temp_sensors: TEMP_SENSORS.iter().map(|sensor| String::from(*sensor)).collect(),
+            drm_path: String::from(DRM_PATH),
+            batt_path: String::from(BATT_PATH),
+            tzs: TZS
+                .iter()
+                .map(|(label, name, format)| {
+                    (*label, String::from(*name), format.map(String::from))
+                })
+                .collect(),
+            time_format: String::from(TIME_FORMAT),
+            net_ifaces: NET_IFACES.iter().map(|iface| String::from(*iface)).collect(),
+            wifi_iface: WIFI_IFACE.map(String::from),
+            vpn_ifaces: VPN_IFACES.iter().map(|iface| String::from(*iface)).collect(),
+            public_ip_url: PUBLIC_IP_URL.map(String::from),
+            public_ip_interval: PUBLIC_IP_INTERVAL,
+            ping_host: PING_HOST.map(String::from),
+            ping_timeout: PING_TIMEOUT,
+            mixer: MIXER.map(String::from),
+            media: MEDIA,
+            media_width: MEDIA_WIDTH,
+            maildirs: MAILDIRS.iter().map(|maildir| String::from(*maildir)).collect(),
+            weather_station: WEATHER_STATION.map(String::from),
+            weather_interval: WEATHER_INTERVAL,
+            batt_mode: BATT_MODE,
+            batt_health: BATT_HEALTH,
+            batt_backend: BATT_BACKEND,
+            mounts: MOUNTS.iter().map(|mount| String::from(*mount)).collect(),
+            commands: vec![],
+            format: StatusFormat {
+                separator: String::from(SEPARATOR),
+                device_separator: String::from(DEVICE_SEPARATOR),
+                sections: SECTIONS
+                    .iter()
+                    .map(|(section, prefix)| (section.clone(), String::from(*prefix)))
+                    .collect(),
+                template: TEMPLATE.map(String::from),
+                markup: MARKUP,
+            },
+            placeholder: String::from(PLACEHOLDER),
+            thresholds: THRESHOLDS
+                .iter()
+                .map(|(section, limit, color)| (section.clone(), *limit, String::from(*color)))
+                .collect(),
+            disk_thresholds: vec![],
+            alerts: ALERTS
+                .iter()
+                .map(|(section, limit, margin, message)| {
+                    (section.clone(), None, *limit, *margin, String::from(*message))
+                })
+                .collect(),
+            interval: INTERVAL,
+            intervals: INTERVALS
+                .iter()
+                .map(|(section, interval)| (section.clone(), *interval))
+                .collect(),
+            outputs: OUTPUTS.to_vec(),
+            fifo: FIFO.map(String::from),
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration file at the provided path or, if no path is
+    /// provided, the one in the default location if it exists.
+    pub fn load(path: Option<&Path>) -> Result<Config, String> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => match Config::default_path() {
+                Some(ref path) if path.exists() => path.clone(),
+                _ => return Ok(Config::default()),
+            },
+        };
+
+        let contents = read_to_string(&path)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        Config::parse(&contents).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    /// Return the default location of the configuration file.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config_home.join("rwmstatus").join("config.toml"))
+    }
+
+    /// Parse the contents of a configuration file.
+    fn parse(contents: &str) -> Result<Config, String> {
+        let mut config = Config::default();
+
+        for (key, value) in toml::parse(contents)? {
+            match key.as_str() {
+                "hwmon_path" => config.hwmon_path = as_string(&key, value)?,
+                "temp_sensors" => config.temp_sensors = as_strings(&key, value)?,
+                "drm_path" => config.drm_path = as_string(&key, value)?,
+                "batt_path" => config.batt_path = as_string(&key, value)?,
+                "interval" => config.interval = as_positive(&key, value)?,
+                "output" => config.outputs = as_outputs(&key, value)?,
+                "fifo" => config.fifo = Some(as_string(&key, value)?),
+                "tz" => config.tzs = as_tzs(&key, value)?,
+                "time_format" => config.time_format = as_string(&key, value)?,
+                "net_ifaces" => config.net_ifaces = as_strings(&key, value)?,
+                "wifi_iface" => config.wifi_iface = Some(as_string(&key, value)?),
+                "vpn_ifaces" => config.vpn_ifaces = as_strings(&key, value)?,
+                "public_ip_url" => config.public_ip_url = Some(as_string(&key, value)?),
+                "public_ip_interval" => config.public_ip_interval = as_positive(&key, value)?,
+                "ping_host" => config.ping_host = Some(as_string(&key, value)?),
+                "ping_timeout" => config.ping_timeout = as_positive(&key, value)?,
+                "mixer" => config.mixer = Some(as_string(&key, value)?),
+                "media" => config.media = as_bool(&key, value)?,
+                "media_width" => config.media_width = as_positive(&key, value)? as usize,
+                "maildirs" => config.maildirs = as_strings(&key, value)?,
+                "weather_station" if cfg!(feature = "weather") => {
+                    config.weather_station = Some(as_string(&key, value)?);
+                }
+                "weather_station" => return Err(format!("{} requires the weather feature", key)),
+                "weather_interval" => config.weather_interval = as_positive(&key, value)?,
+                "batt_mode" => config.batt_mode = as_batt_mode(&key, value)?,
+                "batt_health" => config.batt_health = as_bool(&key, value)?,
+                "batt_backend" => config.batt_backend = as_batt_backend(&key, value)?,
+                "mounts" => config.mounts = as_strings(&key, value)?,
+                "command" => config.commands = as_commands(&key, value)?,
+                "separator" => config.format.separator = as_string(&key, value)?,
+                "device_separator" => config.format.device_separator = as_string(&key, value)?,
+                "placeholder" => config.placeholder = as_string(&key, value)?,
+                "section" => {
+                    let sections = as_sections(&key, value)?;
+                    for (section, _, interval) in &sections {
+                        if let Some(interval) = interval {
+                            config.intervals.retain(|(known, _)| known != section);
+                            config.intervals.push((section.clone(), *interval));
+                        }
+                    }
+                    config.format.sections = sections
+                        .into_iter()
+                        .map(|(section, prefix, _)| (section, prefix))
+                        .collect();
+                }
+                "template" => config.format.template = Some(as_string(&key, value)?),
+                "markup" => config.format.markup = as_markup(&key, value)?,
+                "threshold" => {
+                    config.thresholds.clear();
+                    config.disk_thresholds.clear();
+                    for (section, mount, limit, color) in as_thresholds(&key, value)? {
+                        match mount {
+                            Some(mount) => config.disk_thresholds.push((mount, limit, color)),
+                            None => config.thresholds.push((section, limit, color)),
+                        }
+                    }
+                }
+                "alert" if cfg!(feature = "notify") => config.alerts = as_alerts(&key, value)?,
+                "alert" => return Err(format!("{} requires the notify feature", key)),
+                _ => return Err(format!("unknown key {}", key)),
+            }
+        }
+
+        // Sections which are not built in must have been added by a
+        // `[[command]]` table, which may come after them.
+        for (section, _) in &config.format.sections {
+            if let Section::Monitor(id) = section {
+                if !config.commands.iter().any(|command| command.name == *id) {
+                    return Err(format!("section.name: unknown section {}", id));
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Build the `RwmStatus` described by this configuration.
+    pub fn status(&self) -> RwmStatus {
+        let tzs: Vec<(char, &str, Option<&str>)> = self
+            .tzs
+            .iter()
+            .map(|(label, name, format)| (*label, &name[..], format.as_ref().map(|f| &f[..])))
+            .collect();
+        let mut rwmstatus = RwmStatus::new(
+            Path::new(&self.hwmon_path),
+            Path::new(&self.batt_path),
+            Path::new(BACKLIGHT_PATH),
+            &tzs,
+        );
+
+        rwmstatus.set_format(self.format.clone());
+        rwmstatus.set_placeholder(&self.placeholder);
+        rwmstatus.set_time_format(&self.time_format);
+        let mounts: Vec<&str> = self.mounts.iter().map(|mount| &mount[..]).collect();
+        rwmstatus.set_mounts(&mounts);
+        let net_ifaces: Vec<&str> = self.net_ifaces.iter().map(|iface| &iface[..]).collect();
+        rwmstatus.set_net_ifaces(&net_ifaces);
+        let vpn_ifaces: Vec<&str> = self.vpn_ifaces.iter().map(|iface| &iface[..]).collect();
+        rwmstatus.set_vpn_ifaces(&vpn_ifaces);
+        rwmstatus.set_public_ip(
+            self.public_ip_url.as_deref(),
+            Duration::from_secs(self.public_ip_interval),
+        );
+        rwmstatus.set_temp_unit(TEMP_UNIT);
+        rwmstatus.set_temp_trend(TEMP_TREND);
+        let temp_sensors: Vec<&str> = self.temp_sensors.iter().map(|sensor| &sensor[..]).collect();
+        rwmstatus.set_temp_sensors(&temp_sensors);
+        rwmstatus.set_cpu_per_core(CPU_PER_CORE);
+        rwmstatus.set_cpu_freq(Path::new(CPU_PATH), FREQ_MODE);
+        rwmstatus.set_drm_path(Path::new(&self.drm_path));
+        rwmstatus.set_batt_format(BATT_FORMAT);
+        rwmstatus.set_batt_mode(self.batt_mode);
+        rwmstatus.set_batt_health(self.batt_health);
+        rwmstatus.set_batt_backend(self.batt_backend);
+        rwmstatus.set_wifi_iface(self.wifi_iface.as_deref());
+        rwmstatus.set_mixer(self.mixer.as_deref());
+        rwmstatus.set_media_width(Some(self.media_width).filter(|_| self.media));
+        let maildirs: Vec<String> =
+            self.maildirs.iter().map(|maildir| expand_home(maildir)).collect();
+        let maildirs: Vec<&str> = maildirs.iter().map(|maildir| &maildir[..]).collect();
+        rwmstatus.set_maildirs(&maildirs);
+        #[cfg(feature = "weather")]
+        rwmstatus.set_weather(
+            self.weather_station.as_deref(),
+            Duration::from_secs(self.weather_interval),
+        );
+        for section in Section::ALL.iter() {
+            let interval = self
+                .intervals
+                .iter()
+                .find(|(known, _)| known == section)
+                .map_or(self.interval, |(_, interval)| *interval);
+            rwmstatus.set_interval(section.clone(), Duration::from_secs(interval));
+            // The host is pinged as often as the section is read.
+            if *section == Section::Ping {
+                rwmstatus.set_ping(
+                    self.ping_host.as_deref(),
+                    Duration::from_secs(interval),
+                    Duration::from_secs(self.ping_timeout),
+                );
+            }
+        }
+        for command in &self.commands {
+            rwmstatus.add_monitor(Box::new(CommandMonitor::new(
+                &command.name,
+                &command.command,
+                Duration::from_secs(command.interval.unwrap_or(self.interval)),
+                Duration::from_secs(command.timeout),
+            )));
+        }
+        for (section, interval) in &self.intervals {
+            if let Section::Monitor(_) = section {
+                rwmstatus.set_interval(section.clone(), Duration::from_secs(*interval));
+            }
+        }
+        for (section, limit, color) in &self.thresholds {
+            rwmstatus.add_threshold(section.clone(), *limit, color);
+        }
+        for (mount, limit, color) in &self.disk_thresholds {
+            rwmstatus.add_disk_threshold(mount, *limit, color);
+        }
+        #[cfg(feature = "notify")]
+        for (section, mount, limit, margin, message) in &self.alerts {
+            match mount {
+                Some(mount) => rwmstatus.add_disk_alert(mount, *limit, *margin, message),
+                None => rwmstatus.add_alert(section.clone(), *limit, *margin, message),
+            }
+        }
+        if CHARGE_ANIMATION {
+            rwmstatus.set_charge_animation(&CHARGE_FRAMES[..]);
+        }
+        rwmstatus
+    }
+}
+
+/// Parse the name of an output.
+pub fn parse_output(value: &str) -> Result<Output, String> {
+    match value {
+        "stdout" => Ok(Output::Stdout),
+        "i3bar" | "swaybar" => Ok(Output::I3bar),
+        "x11" if cfg!(feature = "x11") => Ok(Output::X11),
+        "x11" => Err(String::from("x11 output requires the x11 feature")),
+        _ => Err(format!("invalid output {}, must be stdout, i3bar, swaybar, or x11", value)),
+    }
+}
+
+/// Return the outputs held by the value of the provided key, either a single
+/// output or an array of them.
+fn as_outputs(key: &str, value: Value) -> Result<Vec<Output>, String> {
+    let names = match value {
+        Value::String(name) => vec![name],
+        value => as_strings(key, value)
+            .map_err(|_| format!("{} must be a string or an array of strings", key))?,
+    };
+
+    names
+        .iter()
+        .map(|name| parse_output(name).map_err(|err| format!("{}: {}", key, err)))
+        .collect()
+}
+
+/// Replace a leading `~` of the provided path with the home directory.
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix('~'), env::var("HOME")) {
+        (Some(rest), Ok(home)) if rest.is_empty() || rest.starts_with('/') => home + rest,
+        _ => String::from(path),
+    }
+}
+
+/// Return the string held by the value of the provided key.
+fn as_string(key: &str, value: Value) -> Result<String, String> {
+    match value {
+        Value::String(string) => Ok(string),
+        _ => Err(format!("{} must be a string", key)),
+    }
+}
+
+/// Return the boolean value of the provided key.
+fn as_bool(key: &str, value: Value) -> Result<bool, String> {
+    match value {
+        Value::Boolean(boolean) => Ok(boolean),
+        _ => Err(format!("{} must be true or false", key)),
+    }
+}
+
+/// Return the strings held by the array value of the provided key.
+fn as_strings(key: &str, value: Value) -> Result<Vec<String>, String> {
+    let err = || format!("{} must be an array of strings", key);
+    match value {
+        Value::Array(values) => values
+            .into_iter()
+            .map(|value| match value {
+                Value::String(string) => Ok(string),
+                _ => Err(err()),
+            })
+            .collect(),
+        _ => Err(err()),
+    }
+}
+
+/// Return the markup named by the value of the provided key.
+fn as_markup(key: &str, value: Value) -> Result<Markup, String> {
+    match as_string(key, value)?.as_str() {
+        "plain" => Ok(Markup::Plain),
+        "pango" => Ok(Markup::Pango),
+        "status2d" => Ok(Markup::Status2d),
+        markup => Err(format!(
+            "{}: invalid markup {}, must be plain, pango, or status2d",
+            key, markup
+        )),
+    }
+}
+
+/// Return the battery mode named by the value of the provided key.
+fn as_batt_mode(key: &str, value: Value) -> Result<BattMode, String> {
+    match as_string(key, value)?.as_str() {
+        "per-battery" => Ok(BattMode::PerBattery),
+        "combined" => Ok(BattMode::Combined),
+        mode => Err(format!(
+            "{}: invalid battery mode {}, must be per-battery or combined",
+            key, mode
+        )),
+    }
+}
+
+/// Return the battery backend named by the value of the provided key.
+fn as_batt_backend(key: &str, value: Value) -> Result<BattBackend, String> {
+    match as_string(key, value)?.as_str() {
+        "sysfs" => Ok(BattBackend::Sysfs),
+        #[cfg(feature = "upower")]
+        "upower" => Ok(BattBackend::Upower),
+        #[cfg(not(feature = "upower"))]
+        "upower" => Err(format!("{}: upower requires the upower feature", key)),
+        backend => Err(format!(
+            "{}: invalid battery backend {}, must be sysfs or upower",
+            key, backend
+        )),
+    }
+}
+
+/// Return the number held by the value of the provided key.
+fn as_number(key: &str, value: Value) -> Result<f64, String> {
+    match value {
+        Value::Integer(int) => Ok(int as f64),
+        Value::Float(float) => Ok(float),
+        _ => Err(format!("{} must be a number", key)),
+    }
+}
+
+/// Return the positive integer held by the value of the provided key.
+fn as_positive(key: &str, value: Value) -> Result<u64, String> {
+    match value {
+        Value::Integer(int) if int > 0 => Ok(int as u64),
+        _ => Err(format!("{} must be a positive integer", key)),
+    }
+}
+
+/// Return the array of tables held by the value of the provided key.
+fn as_tables(key: &str, value: Value) -> Result<Vec<Table>, String> {
+    let err = || format!("{} must be an array of tables", key);
+    match value {
+        Value::Array(values) => values
+            .into_iter()
+            .map(|value| match value {
+                Value::Table(table) => Ok(table),
+                _ => Err(err()),
+            })
+            .collect(),
+        _ => Err(err()),
+    }
+}
+
+/// Return the sections held by the `[[section]]` tables with their prefixes
+/// and intervals, if any.  A section without a prefix keeps its prefix from
+/// `PREFIXES`.
+fn as_sections(key: &str, value: Value) -> Result<Vec<(Section, String, Option<u64>)>, String> {
+    as_tables(key, value)?
+        .into_iter()
+        .map(|table| {
+            let mut section = None;
+            let mut prefix = None;
+            let mut interval = None;
+            for (section_key, section_value) in table {
+                let section_key = format!("{}.{}", key, section_key);
+                match section_key.as_str() {
+                    "section.name" => {
+                        // Any other name must be that of a `[[command]]`.
+                        let name = as_string(&section_key, section_value)?;
+                        section = Some(
+                            Section::from_name(&name).unwrap_or(Section::Monitor(name)),
+                        );
+                    }
+                    "section.prefix" => prefix = Some(as_string(&section_key, section_value)?),
+                    "section.interval" => {
+                        interval = Some(as_positive(&section_key, section_value)?);
+                    }
+                    _ => return Err(format!("unknown key {}", section_key)),
+                }
+            }
+
+            let section = section.ok_or_else(|| format!("{} requires a name", key))?;
+            let prefix = prefix.unwrap_or_else(|| {
+                PREFIXES
+                    .iter()
+                    .find(|(default, _)| *default == section)
+                    .map(|(_, prefix)| String::from(*prefix))
+                    .unwrap_or_default()
+            });
+            Ok((section, prefix, interval))
+        })
+        .collect()
+}
+
+/// ## ShellCommand
+///
+/// A shell command of a `[[command]]` table whose first line of output is
+/// shown in the section with its name.
+pub struct ShellCommand {
+    pub name: String,
+    pub command: String,
+    /// Time between runs in seconds, `interval` if `None`.
+    pub interval: Option<u64>,
+    /// Time the command may run for in seconds.
+    pub timeout: u64,
+}
+
+/// Return the shell commands held by the `[[command]]` tables.
+fn as_commands(key: &str, value: Value) -> Result<Vec<ShellCommand>, String> {
+    let commands = as_tables(key, value)?
+        .into_iter()
+        .map(|table| {
+            let mut name = None;
+            let mut command = None;
+            let mut interval = None;
+            let mut timeout = COMMAND_TIMEOUT;
+            for (command_key, command_value) in table {
+                let command_key = format!("{}.{}", key, command_key);
+                match command_key.as_str() {
+                    "command.name" => {
+                        let string = as_string(&command_key, command_value)?;
+                        if Section::from_name(&string).is_some() {
+                            let err = format!("{}: {} is a built-in section", command_key, string);
+                            return Err(err);
+                        }
+                        name = Some(string);
+                    }
+                    "command.command" => command = Some(as_string(&command_key, command_value)?),
+                    "command.interval" => {
+                        interval = Some(as_positive(&command_key, command_value)?);
+                    }
+                    "command.timeout" => timeout = as_positive(&command_key, command_value)?,
+                    _ => return Err(format!("unknown key {}", command_key)),
+                }
+            }
+
+            match (name, command) {
+                (Some(name), Some(command)) => Ok(ShellCommand {
+                    name,
+                    command,
+                    interval,
+                    timeout,
+                }),
+                _ => Err(format!("{} requires a name and a command", key)),
+            }
+        })
+        .collect::<Result<Vec<ShellCommand>, String>>()?;
+
+    for (index, command) in commands.iter().enumerate() {
+        if commands[..index].iter().any(|other| other.name == command.name) {
+            return Err(format!("{}.name: duplicate command {}", key, command.name));
+        }
+    }
+    Ok(commands)
+}
+
+/// A threshold of a section, or of a mount point of the disk section, with
+/// its colour.
+type Threshold = (Section, Option<String>, Limit, String);
+
+/// Return the thresholds held by the `[[threshold]]` tables, each with a
+/// section, an optional mount point, either a `below` or an `above` limit,
+/// and a colour.
+fn as_thresholds(key: &str, value: Value) -> Result<Vec<Threshold>, String> {
+    as_tables(key, value)?
+        .into_iter()
+        .map(|table| {
+            let mut section = None;
+            let mut mount = None;
+            let mut limit = None;
+            let mut color = None;
+            for (threshold_key, threshold_value) in table {
+                let threshold_key = format!("{}.{}", key, threshold_key);
+                match threshold_key.as_str() {
+                    "threshold.section" => {
+                        let name = as_string(&threshold_key, threshold_value)?;
+                        section = Some(Section::from_name(&name).ok_or_else(|| {
+                            format!("{}: unknown section {}", threshold_key, name)
+                        })?);
+                    }
+                    "threshold.mount" => mount = Some(as_string(&threshold_key, threshold_value)?),
+                    "threshold.below" => {
+                        limit = Some(Limit::Below(as_number(&threshold_key, threshold_value)?));
+                    }
+                    "threshold.above" => {
+                        limit = Some(Limit::Above(as_number(&threshold_key, threshold_value)?));
+                    }
+                    "threshold.color" => color = Some(as_string(&threshold_key, threshold_value)?),
+                    _ => return Err(format!("unknown key {}", threshold_key)),
+                }
+            }
+
+            if mount.is_some() && section != Some(Section::Disks) {
+                return Err(format!("{}.mount is only valid for the disk section", key));
+            }
+
+            match (section, limit, color) {
+                (Some(section), Some(limit), Some(color)) => Ok((section, mount, limit, color)),
+                _ => Err(format!(
+                    "{} requires a section, a below or above limit, and a color",
+                    key
+                )),
+            }
+        })
+        .collect()
+}
+
+/// An alert of a section, or of a mount point of the disk section, with its
+/// margin and message.
+pub type AlertRule = (Section, Option<String>, Limit, f64, String);
+
+/// Return the alerts held by the `[[alert]]` tables, each with a section, an
+/// optional mount point, either a `below` or an `above` limit, an optional
+/// margin, and a message.
+fn as_alerts(key: &str, value: Value) -> Result<Vec<AlertRule>, String> {
+    as_tables(key, value)?
+        .into_iter()
+        .map(|table| {
+            let mut section = None;
+            let mut mount = None;
+            let mut limit = None;
+            let mut margin = ALERT_MARGIN;
+            let mut message = None;
+            for (alert_key, alert_value) in table {
+                let alert_key = format!("{}.{}", key, alert_key);
+                match alert_key.as_str() {
+                    "alert.section" => {
+                        let name = as_string(&alert_key, alert_value)?;
+                        section = Some(Section::from_name(&name).ok_or_else(|| {
+                            format!("{}: unknown section {}", alert_key, name)
+                        })?);
+                    }
+                    "alert.mount" => mount = Some(as_string(&alert_key, alert_value)?),
+                    "alert.below" => {
+                        limit = Some(Limit::Below(as_number(&alert_key, alert_value)?));
+                    }
+                    "alert.above" => {
+                        limit = Some(Limit::Above(as_number(&alert_key, alert_value)?));
+                    }
+                    "alert.margin" => margin = as_number(&alert_key, alert_value)?,
+                    "alert.message" => message = Some(as_string(&alert_key, alert_value)?),
+                    _ => return Err(format!("unknown key {}", alert_key)),
+                }
+            }
+
+            if mount.is_some() && section != Some(Section::Disks) {
+                return Err(format!("{}.mount is only valid for the disk section", key));
+            }
+
+            match (section, limit, message) {
+                (Some(section), Some(limit), Some(message)) => {
+                    Ok((section, mount, limit, margin, message))
+                }
+                _ => Err(format!(
+                    "{} requires a section, a below or above limit, and a message",
+                    key
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Return the time zones held by the `[[tz]]` tables.
+fn as_tzs(key: &str, value: Value) -> Result<Vec<(char, String, Option<String>)>, String> {
+    as_tables(key, value)?
+        .into_iter()
+        .map(|table| {
+            let mut label = None;
+            let mut name = None;
+            let mut format = None;
+            for (tz_key, tz_value) in table {
+                let tz_key = format!("{}.{}", key, tz_key);
+                match tz_key.as_str() {
+                    "tz.label" => {
+                        let string = as_string(&tz_key, tz_value)?;
+                        let mut chars = string.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(c), None) => label = Some(c),
+                            _ => return Err(format!("{} must be a single character", tz_key)),
+                        }
+                    }
+                    "tz.name" => name = Some(as_string(&tz_key, tz_value)?),
+                    "tz.format" => format = Some(as_string(&tz_key, tz_value)?),
+                    _ => return Err(format!("unknown key {}", tz_key)),
+                }
+            }
+
+            match (label, name) {
+                (Some(label), Some(name)) => Ok((label, name, format)),
+                _ => Err(format!("{} requires a label and a name", key)),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,370 @@
+//! # Events
+//!
+//! Wakes the main loop as soon as something changes instead of at the next
+//! update: kernel uevents report e.g. a plugged in charger or a new battery
+//! and inotify reports changes to watched files such as the configuration
+//! file or to watched directories such as those of new mail.  Commands which
+//! print a line on each change, such as `upower --monitor`, can stand in for
+//! the uevents of a subsystem.  Without them the main loop simply polls, as
+//! it always does on the BSDs which have neither uevents nor inotify.
+
+// std imports
+use std::collections::VecDeque;
+#[cfg(target_os = "linux")]
+use std::ffi::{CString, OsStr, OsString};
+use std::io;
+#[cfg(target_os = "linux")]
+use std::mem;
+#[cfg(target_os = "linux")]
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::process::Child;
+#[cfg(feature = "upower")]
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+// Internal module imports
+use signal;
+
+/// Size of the buffer for a single uevent or a batch of inotify events.
+const BUF_SIZE: usize = 8192;
+
+/// How long to wait for related events after the first one, e.g. for the
+/// battery after its charger, before updating the status.
+pub const SETTLE_TIME: Duration = Duration::from_millis(100);
+
+/// Something which changed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// A kernel uevent, e.g. the `change` action of the `power_supply`
+    /// subsystem.
+    Uevent { action: String, subsystem: String },
+    /// A watched file was written or replaced or an entry was added to or
+    /// removed from a watched directory.
+    Changed(PathBuf),
+}
+
+/// The sources of events.
+pub struct Events {
+    uevent: Option<RawFd>,
+    inotify: Option<RawFd>,
+    /// The watch descriptor of each watched file's directory with the file.
+    #[cfg(target_os = "linux")]
+    watches: Vec<(i32, PathBuf)>,
+    /// The watch descriptor of each watched directory with the directory.
+    #[cfg(target_os = "linux")]
+    dir_watches: Vec<(i32, PathBuf)>,
+    /// The monitoring commands with the subsystem their output stands for.
+    monitors: Vec<(Child, String)>,
+    pending: VecDeque<Event>,
+}
+
+impl Events {
+    /// Subscribe to kernel uevents.  If that is not possible only the
+    /// watched files, if any, wake the main loop early.
+    pub fn new() -> Events {
+        Events {
+            uevent: open_uevent().ok(),
+            inotify: None,
+            #[cfg(target_os = "linux")]
+            watches: vec![],
+            #[cfg(target_os = "linux")]
+            dir_watches: vec![],
+            monitors: vec![],
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Report changes to the file at the provided path.  Its directory is
+    /// watched so that the file may also be replaced, as editors tend to do.
+    #[cfg(target_os = "linux")]
+    pub fn watch(&mut self, path: &Path) -> io::Result<()> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let wd = self.add_watch(dir, libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO)?;
+        self.watches.push((wd, PathBuf::from(path)));
+        Ok(())
+    }
+
+    /// Report entries added to or removed from the directory at the provided
+    /// path, e.g. mail delivered to or read from the `new` directory of a
+    /// Maildir.  Watching a directory again has no effect.
+    #[cfg(target_os = "linux")]
+    pub fn watch_dir(&mut self, dir: &Path) -> io::Result<()> {
+        if self.dir_watches.iter().any(|(_, watched)| watched == dir) {
+            return Ok(());
+        }
+
+        let mask = libc::IN_CREATE | libc::IN_DELETE | libc::IN_MOVED_FROM | libc::IN_MOVED_TO;
+        let wd = self.add_watch(dir, mask)?;
+        self.dir_watches.push((wd, PathBuf::from(dir)));
+        Ok(())
+    }
+
+    /// Add the events of the provided mask to the inotify watch of the
+    /// directory and return its watch descriptor.
+    #[cfg(target_os = "linux")]
+    fn add_watch(&mut self, dir: &Path, mask: u32) -> io::Result<i32> {
+        let inotify = match self.inotify {
+            Some(fd) => fd,
+            None => {
+                let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+                if fd < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                self.inotify = Some(fd);
+                fd
+            }
+        };
+
+        let dir = CString::new(dir.as_os_str().as_bytes())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        // A directory has a single watch, the file and directory watches of
+        // the same directory share it.
+        let mask = mask | libc::IN_MASK_ADD;
+        let wd = unsafe { libc::inotify_add_watch(inotify, dir.as_ptr(), mask) };
+        if wd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(wd)
+    }
+
+    /// Files cannot be watched without inotify.
+    #[cfg(not(target_os = "linux"))]
+    pub fn watch(&mut self, _: &Path) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    /// Directories cannot be watched without inotify.
+    #[cfg(not(target_os = "linux"))]
+    pub fn watch_dir(&mut self, _: &Path) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    /// Run the provided command and report a `change` uevent of the provided
+    /// subsystem each time it prints something, e.g. `upower --monitor` for
+    /// `power_supply`.
+    #[cfg(feature = "upower")]
+    pub fn monitor(&mut self, command: &mut Command, subsystem: &str) -> io::Result<()> {
+        let child = command.stdin(Stdio::null()).stdout(Stdio::piped()).spawn()?;
+        self.monitors.push((child, String::from(subsystem)));
+        Ok(())
+    }
+
+    /// Wait for the next event until the timeout passes or a signal which
+    /// the main loop acts on is received, see `signal::pending`.
+    pub fn wait(&mut self, timeout: Duration) -> Option<Event> {
+        let deadline = Instant::now() + timeout;
+        while self.pending.is_empty() && !signal::pending() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+
+            let monitor_fds = self.monitors
+                .iter()
+                .filter_map(|(child, _)| child.stdout.as_ref().map(AsRawFd::as_raw_fd));
+            let fds: Vec<RawFd> = self.uevent
+                .iter()
+                .chain(self.inotify.iter())
+                .cloned()
+                .chain(monitor_fds)
+                .collect();
+            let mut pollfds: Vec<libc::pollfd> = fds
+                .iter()
+                .map(|fd| libc::pollfd {
+                    fd: *fd,
+                    events: libc::POLLIN,
+                    revents: 0,
+                })
+                .collect();
+            let wait = signal::POLL_INTERVAL.min(deadline - now);
+            let rc = unsafe {
+                libc::poll(
+                    pollfds.as_mut_ptr(),
+                    pollfds.len() as libc::nfds_t,
+                    wait.as_millis() as libc::c_int,
+                )
+            };
+            if rc <= 0 {
+                continue;
+            }
+
+            for pollfd in pollfds.iter().filter(|pollfd| pollfd.revents & libc::POLLIN != 0) {
+                if Some(pollfd.fd) == self.uevent {
+                    self.read_uevent(pollfd.fd);
+                } else if Some(pollfd.fd) == self.inotify {
+                    self.read_inotify(pollfd.fd);
+                } else {
+                    self.read_monitor(pollfd.fd);
+                }
+            }
+            // A monitor which exited would otherwise be reported as
+            // readable over and over.
+            for pollfd in pollfds.iter().filter(|pollfd| pollfd.revents & libc::POLLHUP != 0) {
+                self.stop_monitor(pollfd.fd);
+            }
+        }
+        self.pending.pop_front()
+    }
+
+    /// Read a single uevent: `ACTION@DEVPATH` followed by `KEY=VALUE` pairs,
+    /// all NUL-terminated.
+    fn read_uevent(&mut self, fd: RawFd) {
+        let mut buf = [0u8; BUF_SIZE];
+        let len = unsafe { libc::recv(fd, buf.as_mut_ptr() as *mut libc::c_void, BUF_SIZE, 0) };
+        if len <= 0 {
+            return;
+        }
+
+        let mut action = None;
+        let mut subsystem = None;
+        for field in buf[..len as usize].split(|b| *b == 0) {
+            let field = String::from_utf8_lossy(field);
+            if let Some(value) = field.strip_prefix("ACTION=") {
+                action = Some(String::from(value));
+            } else if let Some(value) = field.strip_prefix("SUBSYSTEM=") {
+                subsystem = Some(String::from(value));
+            }
+        }
+
+        if let (Some(action), Some(subsystem)) = (action, subsystem) {
+            self.pending.push_back(Event::Uevent { action, subsystem });
+        }
+    }
+
+    /// Read the output of a monitoring command and report a change of its
+    /// subsystem.
+    fn read_monitor(&mut self, fd: RawFd) {
+        let mut buf = [0u8; BUF_SIZE];
+        let len = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, BUF_SIZE) };
+        if len <= 0 {
+            return;
+        }
+
+        let monitor = self.monitors
+            .iter()
+            .find(|(child, _)| child.stdout.as_ref().map(AsRawFd::as_raw_fd) == Some(fd));
+        if let Some((_, subsystem)) = monitor {
+            let event = Event::Uevent {
+                action: String::from("change"),
+                subsystem: subsystem.clone(),
+            };
+            if !self.pending.contains(&event) {
+                self.pending.push_back(event);
+            }
+        }
+    }
+
+    /// Stop polling the monitoring command with the provided output.
+    fn stop_monitor(&mut self, fd: RawFd) {
+        let index = self.monitors
+            .iter()
+            .position(|(child, _)| child.stdout.as_ref().map(AsRawFd::as_raw_fd) == Some(fd));
+        if let Some(index) = index {
+            let (mut child, _) = self.monitors.remove(index);
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+
+    /// Read the pending inotify events and report the watched files and
+    /// directories among them.
+    #[cfg(target_os = "linux")]
+    fn read_inotify(&mut self, fd: RawFd) {
+        let mut buf = [0u8; BUF_SIZE];
+        let len = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, BUF_SIZE) };
+        if len <= 0 {
+            return;
+        }
+
+        let header = mem::size_of::<libc::inotify_event>();
+        let mut offset = 0;
+        while offset + header <= len as usize {
+            let event: libc::inotify_event = unsafe {
+                std::ptr::read_unaligned(buf[offset..].as_ptr() as *const libc::inotify_event)
+            };
+            let name_start = offset + header;
+            let name_end = name_start + event.len as usize;
+            let name = buf[name_start..name_end.min(len as usize)]
+                .split(|b| *b == 0)
+                .next()
+                .map(|name| OsString::from(OsStr::from_bytes(name)))
+                .unwrap_or_default();
+            offset = name_end;
+
+            let changed = self.watches.iter().find(|(wd, path)| {
+                *wd == event.wd && path.file_name() == Some(name.as_os_str())
+            });
+            let changed_dir = self.dir_watches.iter().find(|(wd, _)| *wd == event.wd);
+            for (_, path) in changed.into_iter().chain(changed_dir) {
+                let event = Event::Changed(path.clone());
+                if !self.pending.contains(&event) {
+                    self.pending.push_back(event);
+                }
+            }
+        }
+    }
+
+    /// There is no inotify descriptor to read without inotify.
+    #[cfg(not(target_os = "linux"))]
+    fn read_inotify(&mut self, _: RawFd) {}
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        for fd in self.uevent.iter().chain(self.inotify.iter()) {
+            unsafe {
+                libc::close(*fd);
+            }
+        }
+        for (child, _) in &mut self.monitors {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Open a netlink socket subscribed to the kernel's uevents.
+#[cfg(target_os = "linux")]
+fn open_uevent() -> io::Result<RawFd> {
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC | libc::SOCK_NONBLOCK,
+            libc::NETLINK_KOBJECT_UEVENT,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+    addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    // Group 1 carries the kernel's own events.
+    addr.nl_groups = 1;
+    let rc = unsafe {
+        libc::bind(
+            fd,
+            &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+            mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if rc < 0 {
+        let err = io::Error::last_os_error();
+        unsafe {
+            libc::close(fd);
+        }
+        return Err(err);
+    }
+    Ok(fd)
+}
+
+/// Only Linux sends uevents over netlink.
+#[cfg(not(target_os = "linux"))]
+fn open_uevent() -> io::Result<RawFd> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
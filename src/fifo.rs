@@ -0,0 +1,80 @@
+//! # FIFO output
+//!
+//! Writes each status update as a line to a named pipe, e.g. for lemonbar or
+//! dzen2, or appends it to a regular file.
+
+// std imports
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+// Lib imports
+use rwmstatus::StatusSnapshot;
+
+// Internal module imports
+use sink::OutputSink;
+
+/// A FIFO or regular file which is (re)opened on demand.
+pub struct Fifo {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl Fifo {
+    /// Create a sink for the provided path.  The file is only opened on the
+    /// first write.
+    pub fn new(path: &Path) -> Fifo {
+        Fifo {
+            path: PathBuf::from(path),
+            file: None,
+        }
+    }
+
+    /// Write the status as a single line.  Lines up to `PIPE_BUF` bytes long
+    /// are written atomically.  The update is dropped rather than blocking
+    /// the status bar if the FIFO has no reader or its buffer is full, and the
+    /// FIFO is reopened if its reader went away.
+    pub fn write_line(&mut self, status: &str) -> io::Result<()> {
+        let line = format!("{}\n", status);
+
+        // A second attempt is made after reopening a FIFO whose reader left.
+        for _ in 0..2 {
+            if self.file.is_none() {
+                match self.open() {
+                    Ok(file) => self.file = Some(file),
+                    Err(ref err) if err.raw_os_error() == Some(libc::ENXIO) => return Ok(()),
+                    Err(err) => return Err(err),
+                }
+            }
+
+            match self.file.as_mut().unwrap().write_all(line.as_bytes()) {
+                Err(ref err) if err.kind() == io::ErrorKind::BrokenPipe => self.file = None,
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                result => return result,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Open the file for appending without blocking.  Opening a FIFO without
+    /// a reader fails with `ENXIO`.
+    fn open(&self) -> io::Result<File> {
+        OpenOptions::new()
+            .append(true)
+            .create(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&self.path)
+    }
+}
+
+impl OutputSink for Fifo {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn write(&mut self, _: &StatusSnapshot, status: &str) -> io::Result<()> {
+        self.write_line(status)
+    }
+}
@@ -0,0 +1,49 @@
+//! # HTTP endpoint
+//!
+//! A tiny HTTP server which responds to every request with the latest status
+//! snapshot as JSON.
+
+// std imports
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// Lib imports
+use rwmstatus::warn;
+
+/// Start serving the JSON held in `json` on the provided address in a
+/// background thread.
+pub fn serve(addr: &str, json: Arc<Mutex<String>>) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| respond(stream, &json));
+            if let Err(err) = result {
+                warn!("HTTP request failed: {}", err);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Read the request and write back the current JSON.
+fn respond(mut stream: TcpStream, json: &Mutex<String>) -> io::Result<()> {
+    // The request itself is irrelevant, but it is read so that the client
+    // does not see the connection reset.
+    let mut request = [0; 1024];
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    let _ = stream.read(&mut request)?;
+
+    let body = json.lock().unwrap().clone();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+}
@@ -13,181 +13,4631 @@ extern crate chrono;
 extern crate chrono_tz;
 extern crate libc;
 
+// Internal modules
+#[macro_use]
+pub mod log;
+#[cfg(feature = "nl80211")]
+mod nl80211;
+#[cfg(feature = "nvml")]
+mod nvml;
+mod ping;
+mod platform;
+
 // std imports
-use std::fs::read_to_string;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::env;
+use std::ffi::CString;
+use std::fmt::Display;
+use std::fs::{read_link, read_to_string};
+use std::io::{self, Read};
+use std::mem;
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{self, AtomicBool, AtomicUsize};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// External imports
+use chrono::prelude::*;
+
+// Internal module imports
+use platform::{batt_devices, read_batt_time, temp_devices};
+pub use platform::{
+    forget_static_attributes, get_batt_energy, get_batt_power, get_batt_raw, get_fans_raw,
+    get_temp_raw, get_total_batt_raw, get_uptime_raw,
+};
+
+/// The directory the `/proc` and `/sys` files are read from, see `set_root`.
+static ROOT: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Read the `/proc` and `/sys` files which are not given by a path argument,
+/// e.g. `/proc/meminfo` or `/sys/class/net`, from below the provided
+/// directory instead of `/`, e.g. from fixtures in tests or from the root of
+/// a container.  Devices given by a path, such as the hwmon directory of
+/// `get_temp`, are read from that path.  Setting it to `/` reads them from
+/// their usual place again.
+pub fn set_root(root: &Path) {
+    *ROOT.lock().unwrap() = Some(PathBuf::from(root));
+}
+
+/// Return where the provided absolute path is read from, see `set_root`.
+fn rooted(path: &str) -> PathBuf {
+    match *ROOT.lock().unwrap() {
+        Some(ref root) => root.join(path.trim_start_matches('/')),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Read the file at the provided path, e.g. a sysfs attribute.  Errors name
+/// the file.
+fn read_file<P: AsRef<Path>>(path: P) -> Result<String, StatusError> {
+    let path = path.as_ref();
+    read_to_string(path)
+        .map_err(|err| StatusError::File(PathBuf::from(path), Box::new(StatusError::Io(err))))
+}
+
+/// Read the number held by the file at the provided path, e.g. a sysfs
+/// attribute.  Errors name the file.
+fn read_number<T, P>(path: P) -> Result<T, StatusError>
+where
+    T: FromStr,
+    StatusError: From<T::Err>,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    read_file(path)?
+        .trim()
+        .parse()
+        .map_err(|err| StatusError::File(PathBuf::from(path), Box::new(StatusError::from(err))))
+}
+
+/// Run the provided command and return what it printed.  Fails if it cannot
+/// be run or exits unsuccessfully.
+fn run(command: &mut Command) -> Result<String, StatusError> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .output()
+        .map_err(|err| StatusError::Command(program.clone(), err.to_string()))?;
+    if !output.status.success() {
+        return Err(StatusError::Command(program, exit_reason(output.status)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Describe how an unsuccessful command exited, e.g. `exited with 1`.
+fn exit_reason(status: ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("exited with {}", code),
+        None => String::from("killed by a signal"),
+    }
+}
+
+/// Return temperatures read from all the sensors of the provided monitor in
+/// the provided unit, e.g. `45°C|Core 0:47°C`.  Sensors with a label are
+/// prefixed with it.
+pub fn get_temp(hwmon: &Path, unit: TempUnit) -> Result<String, StatusError> {
+    let temp_strs: Vec<String> = get_temp_raw(hwmon)?
+        .iter()
+        .map(|temp| temp.format(unit))
+        .collect();
+    Ok(temp_strs.join("|"))
+}
+
+/// ## TempUnit
+///
+/// The unit in which temperatures are displayed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TempUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+impl TempUnit {
+    /// Convert a sysfs temperature in millidegrees Celsius to whole degrees
+    /// in this unit.  Fahrenheit is rounded to the nearest degree whereas
+    /// Celsius is truncated as it always has been.
+    pub fn degrees(self, millidegrees: f64) -> i64 {
+        match self {
+            TempUnit::Celsius => (millidegrees / 1000.0) as i64,
+            TempUnit::Fahrenheit => (millidegrees * 9.0 / 5000.0 + 32.0).round() as i64,
+        }
+    }
+
+    /// Return the unit symbol, e.g. `°C`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TempUnit::Celsius => "°C",
+            TempUnit::Fahrenheit => "°F",
+        }
+    }
+}
+
+/// ## TempReadout
+///
+/// A single temperature sensor readout.  It is displayed in Celsius with its
+/// label, e.g. `Core 0:45°C`, see `TempReadout::format` for other units.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TempReadout {
+    /// The sensor's temp*_input file.
+    pub input: PathBuf,
+    /// The sensor's label, e.g. `Core 0`, if it has one.
+    pub label: Option<String>,
+    /// The name of the sensor's hwmon device, e.g. `coretemp`, if it has
+    /// one.
+    pub name: Option<String>,
+    /// The temperature in millidegrees Celsius, see `TempUnit::degrees`.
+    pub millidegrees: f64,
+}
+
+impl TempReadout {
+    /// Format the temperature in the provided unit, e.g. `Core 0:113°F`.
+    pub fn format(&self, unit: TempUnit) -> String {
+        format_temp(self.label.as_ref(), unit.degrees(self.millidegrees), unit)
+    }
+
+    /// Return true if the sensor is selected by the provided pattern, see
+    /// `RwmStatus::set_temp_sensors`.  Sensors without a label are known by
+    /// their input, e.g. `temp1`.
+    pub fn matches(&self, pattern: &str) -> bool {
+        let label = match self.label {
+            Some(ref label) => label.clone(),
+            None => device_name(&self.input).trim_end_matches("_input").to_string(),
+        };
+        let name = self.name.as_deref().unwrap_or_default();
+        glob_match(pattern, &label) || glob_match(pattern, &format!("{}:{}", name, label))
+    }
+
+    /// Return the readout as a JSON object.
+    pub fn to_json(&self) -> String {
+        let or_null = |value: &Option<String>| match *value {
+            Some(ref value) => json_string(value),
+            None => String::from("null"),
+        };
+        format!(
+            "{{\"input\":{},\"label\":{},\"name\":{},\"millidegrees\":{}}}",
+            json_string(&self.input.to_string_lossy()),
+            or_null(&self.label),
+            or_null(&self.name),
+            self.millidegrees
+        )
+    }
+}
+
+impl std::fmt::Display for TempReadout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.format(TempUnit::Celsius))
+    }
+}
+
+/// ## FanReadout
+///
+/// A single fan speed readout.  It is displayed with its label, e.g.
+/// `CPU:2100`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FanReadout {
+    /// The fan's fan*_input file.
+    pub input: PathBuf,
+    /// The fan's label, e.g. `CPU`, if it has one.
+    pub label: Option<String>,
+    /// The speed in revolutions per minute.
+    pub rpm: u64,
+}
+
+impl std::fmt::Display for FanReadout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.label {
+            Some(ref label) => write!(f, "{}:{}", label, self.rpm),
+            None => write!(f, "{}", self.rpm),
+        }
+    }
+}
+
+/// Return the speeds of all the fans of the provided monitor in RPM, e.g.
+/// `2100|0`.  Fans with a label are prefixed with it.
+pub fn get_fans(hwmon: &Path) -> Result<String, StatusError> {
+    let fan_strs: Vec<String> = get_fans_raw(hwmon)?.iter().map(|fan| fan.to_string()).collect();
+    Ok(fan_strs.join("|"))
+}
+
+/// Format a temperature with an optional sensor label.
+fn format_temp(label: Option<&String>, degrees: i64, unit: TempUnit) -> String {
+    match label {
+        Some(label) => format!("{}:{:02}{}", label, degrees, unit.symbol()),
+        None => format!("{:02}{}", degrees, unit.symbol()),
+    }
+}
+
+/// Time after which `ipmitool` is killed, e.g. because the BMC is slow.
+#[cfg(feature = "ipmi")]
+const IPMITOOL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Return temperatures reported by IPMI/BMC sensors.  The hwmon devices
+/// backed by the `ipmi` driver are read if there are any, otherwise `ipmitool
+/// sdr` is used as a fallback.  Each readout is labelled with the sensor
+/// location, e.g. `inlet:23°C`.  Devices which cannot be read are left out,
+/// it fails only if none of them can be read.
+#[cfg(feature = "ipmi")]
+pub fn get_ipmi_temps(hw_mons: &[PathBuf], unit: TempUnit) -> Result<String, StatusError> {
+    let temps = if hw_mons.is_empty() {
+        get_ipmitool_temps()?
+    } else {
+        let mut temps = vec![];
+        let mut error = None;
+        for hw_mon in hw_mons {
+            match get_temp_raw(hw_mon) {
+                Ok(readouts) => temps.extend(readouts.into_iter().map(|temp| {
+                    (temp.label.unwrap_or_else(|| "ipmi".into()), temp.millidegrees)
+                })),
+                Err(err) => error = Some(err),
+            }
+        }
+        match error {
+            Some(err) if temps.is_empty() => return Err(err),
+            _ => temps,
+        }
+    };
+
+    if temps.is_empty() {
+        return Err(StatusError::NotPresent(String::from("IPMI temperature sensor")));
+    }
+
+    let temp_strs: Vec<String> = temps
+        .iter()
+        .map(|(name, val)| {
+            let label = ipmi_label(name);
+            format_temp(Some(&label), unit.degrees(*val), unit)
+        })
+        .collect();
+    Ok(temp_strs.join("|"))
+}
+
+/// Read the temperature sensors listed by `ipmitool sdr type Temperature` in
+/// millidegrees Celsius.
+#[cfg(feature = "ipmi")]
+fn get_ipmitool_temps() -> Result<Vec<(String, f64)>, StatusError> {
+    let mut ipmitool = Command::new("ipmitool");
+    let output = run_timeout(ipmitool.args(["sdr", "type", "Temperature"]), IPMITOOL_TIMEOUT)
+        .map_err(|reason| StatusError::Command(String::from("ipmitool"), reason))?;
+
+    // Lines are of the form "Inlet Temp | 04h | ok | 7.1 | 23 degrees C".
+    let temps = output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+            let val: f64 = fields.get(4)?.split_whitespace().next()?.parse().ok()?;
+            Some((fields[0].to_string(), val * 1000.0))
+        })
+        .collect();
+    Ok(temps)
+}
+
+/// Return true if there is a BMC which `ipmitool` can read, i.e. the device
+/// of the `ipmi_devintf` driver and the `ipmitool` program.
+#[cfg(feature = "ipmi")]
+fn has_ipmitool() -> bool {
+    let has_bmc = ["/dev/ipmi0", "/dev/ipmi/0", "/dev/ipmidev/0"]
+        .iter()
+        .any(|dev| Path::new(dev).exists());
+    has_bmc
+        && env::var_os("PATH").is_some_and(|paths| {
+            env::split_paths(&paths).any(|dir| dir.join("ipmitool").is_file())
+        })
+}
+
+/// Map an IPMI sensor name to a short location label.
+#[cfg(feature = "ipmi")]
+fn ipmi_label(sensor: &str) -> String {
+    let sensor = sensor.trim().to_lowercase();
+    if sensor.contains("inlet") || sensor.contains("ambient") {
+        String::from("inlet")
+    } else if sensor.contains("exhaust") || sensor.contains("outlet") {
+        String::from("exhaust")
+    } else {
+        String::from(sensor.split_whitespace().next().unwrap_or("ipmi"))
+    }
+}
+
+/// ## GpuReadout
+///
+/// A single GPU readout.  It is displayed in Celsius with its utilization,
+/// e.g. `67°C 45%`, see `GpuReadout::format` for other units.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GpuReadout {
+    /// The GPU's DRM card, e.g. `card0`, or its NVML index, e.g. `nvidia0`.
+    pub card: String,
+    /// The GPU's driver, e.g. `amdgpu`, or its model if known.
+    pub name: Option<String>,
+    /// The temperature in millidegrees Celsius, see `TempUnit::degrees`.
+    pub millidegrees: Option<f64>,
+    /// The utilization as a percentage.
+    pub busy: Option<f64>,
+    /// The shader clock in MHz.
+    pub clock: Option<u64>,
+    /// The power draw in watts.
+    pub power: Option<f64>,
+}
+
+impl GpuReadout {
+    /// Format the temperature in the provided unit with the utilization, e.g.
+    /// `153°F 45%`.  GPUs which report neither show their clock instead.
+    pub fn format(&self, unit: TempUnit) -> String {
+        let mut parts = vec![];
+        if let Some(millidegrees) = self.millidegrees {
+            parts.push(format_temp(None, unit.degrees(millidegrees), unit));
+        }
+        if let Some(busy) = self.busy {
+            parts.push(format!("{:.0}%", busy));
+        }
+        if parts.is_empty() {
+            parts.extend(self.clock.map(|clock| format!("{}MHz", clock)));
+        }
+        parts.join(" ")
+    }
+
+    /// Return the readout as a JSON object.
+    pub fn to_json(&self) -> String {
+        let or_null = |value: Option<String>| value.unwrap_or_else(|| String::from("null"));
+        format!(
+            "{{\"card\":{},\"name\":{},\"millidegrees\":{},\"busy\":{},\"clock\":{},\
+             \"power\":{}}}",
+            json_string(&self.card),
+            or_null(self.name.as_ref().map(|name| json_string(name))),
+            or_null(self.millidegrees.map(|millidegrees| millidegrees.to_string())),
+            or_null(self.busy.map(|busy| busy.to_string())),
+            or_null(self.clock.map(|clock| clock.to_string())),
+            or_null(self.power.map(|power| power.to_string()))
+        )
+    }
+}
+
+impl std::fmt::Display for GpuReadout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.format(TempUnit::Celsius))
+    }
+}
+
+/// Return the temperature and utilization of the GPU of the provided DRM
+/// card, e.g. `67°C 45%`.
+pub fn get_gpu(card: &Path) -> Result<String, StatusError> {
+    Ok(get_gpu_raw(card)?.to_string())
+}
+
+/// Read the GPU of the provided DRM card, e.g. `/sys/class/drm/card0`.  The
+/// temperature and power come from the hwmon device of the GPU, as provided
+/// by `amdgpu`, the utilization from `gpu_busy_percent`, and the clock from
+/// the hwmon device or from `gt_cur_freq_mhz` for `i915`.  Readouts the
+/// driver does not provide are left out.
+pub fn get_gpu_raw(card: &Path) -> Result<GpuReadout, StatusError> {
+    let device = card.join("device");
+    let hwmon = RwmStatus::get_paths(&device.join("hwmon"), "hwmon").into_iter().next();
+    let read = |path: PathBuf| -> Option<f64> { read_to_string(path).ok()?.trim().parse().ok() };
+
+    let millidegrees = hwmon.as_ref().and_then(|hwmon| {
+        let temps = get_temp_raw(hwmon).ok()?;
+        // amdgpu also reports the junction and memory temperatures.
+        let edge = temps.iter().find(|temp| temp.label.as_deref() == Some("edge"));
+        edge.or(temps.first()).map(|temp| temp.millidegrees)
+    });
+    let busy = read(device.join("gpu_busy_percent"));
+    let clock = hwmon
+        .as_ref()
+        .and_then(|hwmon| read(hwmon.join("freq1_input")))
+        .map(|hz| hz / 1_000_000.0)
+        .or_else(|| read(card.join("gt_cur_freq_mhz")))
+        .map(|mhz| mhz as u64);
+    let power = hwmon
+        .as_ref()
+        .and_then(|hwmon| {
+            read(hwmon.join("power1_average")).or_else(|| read(hwmon.join("power1_input")))
+        })
+        .map(|microwatts| microwatts / 1_000_000.0);
+
+    if millidegrees.is_none() && busy.is_none() && clock.is_none() && power.is_none() {
+        return Err(StatusError::NotPresent(format!("GPU readout in {}", card.display())));
+    }
+
+    Ok(GpuReadout {
+        card: device_name(card),
+        name: read_link(device.join("driver")).ok().map(|driver| device_name(&driver)),
+        millidegrees,
+        busy,
+        clock,
+        power,
+    })
+}
+
+/// Read the NVIDIA GPUs through NVML.
+#[cfg(feature = "nvml")]
+pub fn get_nvml_gpus() -> Result<Vec<GpuReadout>, StatusError> {
+    nvml::get_gpus()
+}
+
+/// Return the three load average values.
+pub fn get_load_avgs() -> Result<String, StatusError> {
+    get_load_avgs_n(3)
+}
+
+/// Return the first `count` load average values, i.e. the 1, 5, and 15
+/// minute averages in that order.  `count` must be between 1 and 3.
+pub fn get_load_avgs_n(count: usize) -> Result<String, StatusError> {
+    if !(1..=3).contains(&count) {
+        return Err(StatusError::InvalidArg(
+            format!("{} load averages requested, must be 1-3", count),
+        ));
+    }
+
+    let mut avgs: [libc::c_double; 3] = [0.0; 3];
+
+    // getloadavg returns the number of samples retrieved or -1 on error.
+    let rc = unsafe { libc::getloadavg(avgs.as_mut_ptr(), count as libc::c_int) };
+    if rc < count as libc::c_int {
+        return Err(StatusError::System(rc));
+    }
+
+    let avg_strs: Vec<String> = avgs[..count].iter().map(|avg| format!("{:.2}", avg)).collect();
+    Ok(avg_strs.join(" "))
+}
+
+/// Return the idle and total CPU time in jiffies since boot as read from the
+/// aggregate line of `/proc/stat`.
+pub fn get_cpu_times() -> Result<(u64, u64), StatusError> {
+    parse_cpu_stat(&read_file(rooted("/proc/stat"))?)
+}
+
+/// Return the idle and total CPU time in jiffies since boot of each core in
+/// the order of their index as read from `/proc/stat`.
+pub fn get_core_times() -> Result<Vec<(u64, u64)>, StatusError> {
+    parse_core_stats(&read_file(rooted("/proc/stat"))?)
+}
+
+/// Parse the idle and total CPU times from the contents of `/proc/stat`.
+fn parse_cpu_stat(contents: &str) -> Result<(u64, u64), StatusError> {
+    let line = contents
+        .lines()
+        .find(|line| line.starts_with("cpu "))
+        .ok_or_else(|| StatusError::NotPresent(String::from("cpu line in /proc/stat")))?;
+    parse_cpu_line(line)
+}
+
+/// Parse the idle and total CPU times of each core from the contents of
+/// `/proc/stat`.
+fn parse_core_stats(contents: &str) -> Result<Vec<(u64, u64)>, StatusError> {
+    contents
+        .lines()
+        .filter(|line| {
+            line.strip_prefix("cpu")
+                .and_then(|rest| rest.chars().next())
+                .is_some_and(|c| c.is_ascii_digit())
+        })
+        .map(parse_cpu_line)
+        .collect()
+}
+
+/// Parse the idle and total CPU times from a `cpu` line of `/proc/stat`.
+fn parse_cpu_line(line: &str) -> Result<(u64, u64), StatusError> {
+    // user nice system idle iowait irq softirq steal, guest time is already
+    // included in user and nice.
+    let mut times = [0u64; 8];
+    for (time, field) in times.iter_mut().zip(line.split_whitespace().skip(1)) {
+        *time = field.parse()?;
+    }
+
+    let idle = times[3] + times[4];
+    let total = times.iter().sum();
+    Ok((idle, total))
+}
+
+/// Return the CPU usage in percent between two `(idle, total)` samples or
+/// `None` if no time elapsed between them.
+fn cpu_usage(prev: (u64, u64), curr: (u64, u64)) -> Option<f64> {
+    let idle = curr.0.saturating_sub(prev.0);
+    let total = curr.1.saturating_sub(prev.1);
+    if total == 0 {
+        return None;
+    }
+    Some(100.0 * (1.0 - (idle as f64) / (total as f64)))
+}
+
+/// Format a CPU usage, e.g. `37%`, or `--%` if it is unknown.
+fn format_cpu_usage(usage: Option<f64>) -> String {
+    match usage {
+        Some(usage) => format!("{:.0}%", usage),
+        None => String::from("--%"),
+    }
+}
+
+/// ## FreqMode
+///
+/// Selects how the frequencies of several CPUs are combined.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FreqMode {
+    /// The highest frequency.
+    Max,
+    /// The average frequency.
+    Average,
+}
+
+/// Return the highest or average current frequency of the CPUs in the
+/// provided directory, e.g. `2.4GHz`.
+pub fn get_cpu_freq(cpus: &Path, mode: FreqMode) -> Result<String, StatusError> {
+    Ok(format!("{:.1}GHz", combine_freqs(&get_cpu_freqs(cpus)?, mode) / 1_000_000.0))
+}
+
+/// Return the highest or average of the provided frequencies.
+fn combine_freqs(freqs: &[u64], mode: FreqMode) -> f64 {
+    match mode {
+        FreqMode::Max => freqs.iter().max().cloned().unwrap_or(0) as f64,
+        FreqMode::Average => freqs.iter().sum::<u64>() as f64 / freqs.len().max(1) as f64,
+    }
+}
+
+/// Return the current frequency in kHz of each CPU in the provided
+/// directory, e.g. `/sys/devices/system/cpu`, which supports cpufreq in the
+/// order of their index.
+pub fn get_cpu_freqs(cpus: &Path) -> Result<Vec<u64>, StatusError> {
+    let mut inputs: Vec<(u64, PathBuf)> = cpus
+        .read_dir()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let index = entry.file_name().to_str()?.strip_prefix("cpu")?.parse().ok()?;
+            let input = entry.path().join("cpufreq").join("scaling_cur_freq");
+            if input.exists() {
+                Some((index, input))
+            } else {
+                None
+            }
+        })
+        .collect();
+    inputs.sort();
+
+    if inputs.is_empty() {
+        return Err(StatusError::NotPresent(format!("cpufreq in {}", cpus.display())));
+    }
+    inputs
+        .iter()
+        .map(|(_, input)| read_number(input))
+        .collect()
+}
+
+/// ## MemoryReadout
+///
+/// The memory and swap usage.  It is displayed like `get_mem`, e.g.
+/// `4.2G/15.5G`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MemoryReadout {
+    /// The total memory in bytes.
+    pub total: u64,
+    /// The memory which is not available to new programs in bytes.
+    pub used: u64,
+    /// The total swap space in bytes, 0 without any swap.
+    pub swap_total: u64,
+    /// The used swap space in bytes.
+    pub swap_used: u64,
+}
+
+impl MemoryReadout {
+    /// Return the used memory as a percentage of the total.
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        (self.used as f64) / (self.total as f64) * 100.0
+    }
+
+    /// Return the readout as a JSON object.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"total\":{},\"used\":{},\"swap_total\":{},\"swap_used\":{}}}",
+            self.total, self.used, self.swap_total, self.swap_used
+        )
+    }
+}
+
+impl std::fmt::Display for MemoryReadout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}/{}", human_size(self.used), human_size(self.total))
+    }
+}
+
+/// Return the used and total memory, e.g. `4.2G/15.5G`.
+pub fn get_mem() -> Result<String, StatusError> {
+    Ok(get_mem_raw()?.to_string())
+}
+
+/// Return the used swap space, e.g. `0.3G`.
+pub fn get_swap() -> Result<String, StatusError> {
+    Ok(human_size(get_mem_raw()?.swap_used))
+}
+
+/// Return the memory and swap usage from `/proc/meminfo`.
+pub fn get_mem_raw() -> Result<MemoryReadout, StatusError> {
+    let contents = read_file(rooted("/proc/meminfo"))?;
+    let meminfo = parse_meminfo(&contents)?;
+    let field = |name: &str| {
+        meminfo.get(name).cloned().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} missing from /proc/meminfo", name),
+            )
+        })
+    };
+
+    let total = field("MemTotal")?;
+    // MemAvailable is not provided by kernels older than 3.14.
+    let available = match field("MemAvailable") {
+        Ok(available) => available,
+        Err(_) => field("MemFree")? + field("Buffers")? + field("Cached")?,
+    };
+
+    // Swap is missing if the kernel was built without it.
+    let swap_total = field("SwapTotal").unwrap_or(0);
+    let swap_free = field("SwapFree").unwrap_or(0);
+
+    Ok(MemoryReadout {
+        total: total * 1024,
+        used: total.saturating_sub(available) * 1024,
+        swap_total: swap_total * 1024,
+        swap_used: swap_total.saturating_sub(swap_free) * 1024,
+    })
+}
+
+/// Parse the contents of `/proc/meminfo` into a map of field name to value
+/// in kB.
+fn parse_meminfo(contents: &str) -> Result<HashMap<&str, u64>, StatusError> {
+    let mut meminfo = HashMap::new();
+    for line in contents.lines() {
+        let mut fields = line.split_whitespace();
+        if let (Some(name), Some(value)) = (fields.next(), fields.next()) {
+            meminfo.insert(name.trim_end_matches(':'), value.parse()?);
+        }
+    }
+    Ok(meminfo)
+}
+
+/// Format a number of bytes in a human readable form, e.g. `4.2G`.
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{}{}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1}{}", size, UNITS[unit])
+    }
+}
+
+/// ## DiskReadout
+///
+/// The space of a file system.  It is displayed like `get_disk`, e.g.
+/// `~:412.0G`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiskReadout {
+    /// The mount point of the file system.
+    pub mount: PathBuf,
+    /// The size of the file system in bytes.
+    pub total: u64,
+    /// The used space in bytes.
+    pub used: u64,
+    /// The space available to unprivileged users in bytes.  Unlike the total
+    /// minus the used space it leaves out the blocks reserved for root.
+    pub available: u64,
+}
+
+impl DiskReadout {
+    /// Return the available space as a percentage of the space usable by
+    /// unprivileged users, as `df` does.
+    pub fn percent_free(&self) -> f64 {
+        let usable = self.used + self.available;
+        if usable == 0 {
+            return 0.0;
+        }
+        (self.available as f64) / (usable as f64) * 100.0
+    }
+
+    /// Return the mount point shortened for display: the home directory is
+    /// `~`.
+    pub fn label(&self) -> String {
+        match env::var_os("HOME") {
+            Some(ref home) if Path::new(home) == self.mount => String::from("~"),
+            _ => self.mount.display().to_string(),
+        }
+    }
+
+    /// Return the readout as a JSON object.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"mount\":{},\"total\":{},\"used\":{},\"available\":{}}}",
+            json_string(&self.mount.to_string_lossy()),
+            self.total,
+            self.used,
+            self.available
+        )
+    }
+}
+
+impl std::fmt::Display for DiskReadout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.label(), human_size(self.available))
+    }
+}
+
+/// Return the available space of the file system mounted at the provided
+/// path labelled with the mount point, e.g. `/:23.0G`.
+pub fn get_disk(mount: &Path) -> Result<String, StatusError> {
+    Ok(get_disk_raw(mount)?.to_string())
+}
+
+/// Return the space of the file system mounted at the provided path.
+pub fn get_disk_raw(mount: &Path) -> Result<DiskReadout, StatusError> {
+    let path = CString::new(mount.as_os_str().as_bytes())
+        .map_err(|_| StatusError::InvalidArg(format!("{} contains a nul", mount.display())))?;
+    let mut stat: libc::statvfs = unsafe { mem::zeroed() };
+
+    // statvfs returns 0 on success and -1 on error.
+    let rc = unsafe { libc::statvfs(path.as_ptr(), &mut stat) };
+    if rc != 0 {
+        return Err(StatusError::System(rc));
+    }
+
+    let block_size = stat.f_frsize as u64;
+    Ok(DiskReadout {
+        mount: PathBuf::from(mount),
+        total: stat.f_blocks as u64 * block_size,
+        used: (stat.f_blocks as u64).saturating_sub(stat.f_bfree as u64) * block_size,
+        available: stat.f_bavail as u64 * block_size,
+    })
+}
+
+/// Return the time since boot, e.g. `up 3d 4:12` or `up 2:05` when under a
+/// day.
+pub fn get_uptime() -> Result<String, StatusError> {
+    Ok(format_uptime(get_uptime_raw()?))
+}
+
+/// Format an uptime as days, hours, and minutes.
+fn format_uptime(uptime: Duration) -> String {
+    let mins = uptime.as_secs() / 60;
+    let (days, hours, mins) = (mins / (24 * 60), mins / 60 % 24, mins % 60);
+    if days > 0 {
+        format!("up {}d {}:{:02}", days, hours, mins)
+    } else {
+        format!("up {}:{:02}", hours, mins)
+    }
+}
+
+/// Return the total bytes received and transmitted by the provided network
+/// interface.
+pub fn get_net_bytes(iface: &str) -> Result<(u64, u64), StatusError> {
+    let stats = rooted("/sys/class/net").join(iface).join("statistics");
+    let read = |name: &str| -> Result<u64, StatusError> { read_number(stats.join(name)) };
+    Ok((read("rx_bytes")?, read("tx_bytes")?))
+}
+
+/// Return the name of the network interface of the default route.  If there
+/// are several default routes the one with the lowest metric is used.
+pub fn get_default_iface() -> Result<String, StatusError> {
+    parse_default_iface(&read_file(rooted("/proc/net/route"))?)
+        .ok_or_else(|| StatusError::NotPresent(String::from("default route")))
+}
+
+/// Parse the interface of the default route from the contents of
+/// `/proc/net/route`.
+fn parse_default_iface(contents: &str) -> Option<String> {
+    // Iface Destination Gateway Flags RefCnt Use Metric Mask MTU Window IRTT
+    const RTF_UP: u32 = 0x1;
+
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let flags = u32::from_str_radix(fields.get(3)?, 16).ok()?;
+            let metric: u64 = fields.get(6)?.parse().ok()?;
+            if fields[1] == "00000000" && fields[7] == "00000000" && flags & RTF_UP != 0 {
+                Some((metric, String::from(fields[0])))
+            } else {
+                None
+            }
+        })
+        .min()
+        .map(|(_, iface)| iface)
+}
+
+/// Resolve a configured network interface into its label and the interfaces
+/// whose traffic is added up: `default` is the interface of the default
+/// route and a pattern with `*` or `?` wildcards matches all the interfaces
+/// except for the loopback, e.g. `wl*`.  Other names are used as they are.
+fn resolve_net_ifaces(iface: &str) -> Result<(String, Vec<String>), StatusError> {
+    if iface == "default" {
+        let default = get_default_iface()?;
+        return Ok((default.clone(), vec![default]));
+    }
+    if !iface.contains(['*', '?']) {
+        return Ok((String::from(iface), vec![String::from(iface)]));
+    }
+
+    let mut ifaces: Vec<String> = rooted("/sys/class/net")
+        .read_dir()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name != "lo" && glob_match(iface, name))
+        .collect();
+    if ifaces.is_empty() {
+        return Err(StatusError::NotPresent(format!("network interface matching {}", iface)));
+    }
+    ifaces.sort_by_key(|name| natural_key(name));
+    Ok((String::from(iface), ifaces))
+}
+
+/// Return true if the name matches the pattern in which `*` matches any
+/// sequence of characters and `?` any single character.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // The position after the last `*` and the name position it was tried at.
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p + 1, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Format the receive and transmit rates per second between two `(rx, tx)`
+/// byte count samples taken `secs` apart, e.g. `↓1.2M ↑64.0K`, or return
+/// `None` if no time elapsed between them.  A counter which went backwards,
+/// e.g. after wrapping around, counts as no traffic.
+fn net_rates(prev: (u64, u64), curr: (u64, u64), secs: f64) -> Option<String> {
+    if secs <= 0.0 {
+        return None;
+    }
+
+    let rate = |prev: u64, curr: u64| human_size((curr.saturating_sub(prev) as f64 / secs) as u64);
+    Some(format!("↓{} ↑{}", rate(prev.0, curr.0), rate(prev.1, curr.1)))
+}
+
+/// Return the ESSID and link quality of the provided wireless interface, e.g.
+/// `mynet 72%`, or `down` if it is not connected.
+pub fn get_wifi(iface: &str) -> Result<String, StatusError> {
+    if !rooted("/sys/class/net").join(iface).exists() {
+        return Err(StatusError::NotPresent(String::from(iface)));
+    }
+
+    // The wireless extensions are a fallback for kernels without nl80211.
+    #[cfg(feature = "nl80211")]
+    let link = get_nl80211_link(iface).or_else(|_| get_wext_link(iface))?;
+    #[cfg(not(feature = "nl80211"))]
+    let link = get_wext_link(iface)?;
+
+    match link {
+        Some((essid, Some(percentage))) => {
+            if essid.is_empty() {
+                Ok(format!("{:.0}%", percentage))
+            } else {
+                Ok(format!("{} {:.0}%", essid, percentage))
+            }
+        }
+        Some((essid, None)) => Ok(essid),
+        None => Ok(String::from("down")),
+    }
+}
+
+/// Return the ESSID the provided wireless interface is connected to and its
+/// signal strength as a percentage, if known, or `None` if it is not
+/// connected.
+#[cfg(feature = "nl80211")]
+fn get_nl80211_link(iface: &str) -> Result<Option<(String, Option<f64>)>, StatusError> {
+    // The signal strength is mapped linearly from -100 dBm (0%) to -50 dBm
+    // (100%), as NetworkManager does.
+    let link = nl80211::get_link(iface)?;
+    Ok(link.map(|(essid, signal)| {
+        let percentage = signal.map(|dbm| (2.0 * (f64::from(dbm) + 100.0)).clamp(0.0, 100.0));
+        (essid, percentage)
+    }))
+}
+
+/// Return the ESSID the provided wireless interface is connected to and its
+/// link quality as a percentage using the wireless extensions, or `None` if it
+/// is not connected.
+fn get_wext_link(iface: &str) -> Result<Option<(String, Option<f64>)>, StatusError> {
+    let operstate = read_file(rooted("/sys/class/net").join(iface).join("operstate"))?;
+    if operstate.trim() != "up" {
+        return Ok(None);
+    }
+
+    // Without the ESSID, e.g. if the driver does not support the ioctl, only
+    // the link quality is known.
+    let essid = match get_essid(iface) {
+        Ok(ref essid) if essid.is_empty() => return Ok(None),
+        Ok(essid) => essid,
+        Err(_) => String::new(),
+    };
+
+    let quality = parse_wireless(&read_file(rooted("/proc/net/wireless"))?, iface)?;
+    Ok(Some((essid, Some((quality / 70.0 * 100.0).min(100.0)))))
+}
+
+/// Parse the link quality of the provided interface from the contents of
+/// `/proc/net/wireless`.
+fn parse_wireless(contents: &str, iface: &str) -> Result<f64, StatusError> {
+    // The first two lines are headers, the rest are of the form
+    // "wlan0: 0000   54.  -56.  -256   0   0   0   0   0   0".
+    for line in contents.lines().skip(2) {
+        let mut fields = line.split_whitespace();
+        if fields.next().map(|name| name.trim_end_matches(':')) != Some(iface) {
+            continue;
+        }
+
+        let quality = fields
+            .nth(1)
+            .ok_or_else(|| StatusError::NotPresent(format!("{} link quality", iface)))?;
+        return Ok(quality.trim_end_matches('.').parse::<u32>()? as f64);
+    }
+
+    Err(StatusError::NotPresent(String::from(iface)))
+}
+
+/// Wireless extensions ioctl to get the ESSID.
+const SIOCGIWESSID: libc::c_ulong = 0x8B1B;
+
+/// Maximum length of an ESSID.
+const IW_ESSID_MAX_SIZE: usize = 32;
+
+/// `struct iw_point` from `linux/wireless.h`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IwPoint {
+    pointer: *mut libc::c_void,
+    length: u16,
+    flags: u16,
+}
+
+/// `union iwreq_data` from `linux/wireless.h`.  Only the ESSID member is
+/// used, the padding gives the union its full size.
+#[repr(C)]
+union IwReqData {
+    essid: IwPoint,
+    _pad: [u8; 16],
+}
+
+/// `struct iwreq` from `linux/wireless.h`.
+#[repr(C)]
+struct IwReq {
+    name: [libc::c_char; libc::IFNAMSIZ],
+    data: IwReqData,
+}
+
+/// Return the ESSID the provided wireless interface is connected to.
+fn get_essid(iface: &str) -> Result<String, StatusError> {
+    let mut essid = [0u8; IW_ESSID_MAX_SIZE + 1];
+    let mut req = IwReq {
+        name: [0; libc::IFNAMSIZ],
+        data: IwReqData {
+            essid: IwPoint {
+                pointer: essid.as_mut_ptr() as *mut libc::c_void,
+                length: essid.len() as u16,
+                flags: 0,
+            },
+        },
+    };
+    for (dst, src) in req.name.iter_mut().zip(iface.bytes().take(libc::IFNAMSIZ - 1)) {
+        *dst = src as libc::c_char;
+    }
+
+    let rc = unsafe {
+        let sock = libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0);
+        if sock < 0 {
+            return Err(StatusError::Io(std::io::Error::last_os_error()));
+        }
+        let rc = libc::ioctl(sock, SIOCGIWESSID as _, &mut req as *mut IwReq);
+        libc::close(sock);
+        rc
+    };
+    if rc < 0 {
+        return Err(StatusError::System(rc));
+    }
+
+    let length = (unsafe { req.data.essid.length } as usize).min(IW_ESSID_MAX_SIZE);
+    Ok(String::from_utf8_lossy(&essid[..length]).into_owned())
+}
+
+/// Return the interfaces matching the provided VPN interface which are up,
+/// e.g. `wg0`.  A pattern with `*` or `?` wildcards may match several
+/// interfaces, e.g. `tun*`, and an interface which does not exist is down.
+pub fn get_vpn_ifaces(iface: &str) -> Result<Vec<String>, StatusError> {
+    let ifaces = match resolve_net_ifaces(iface) {
+        Ok((_, ifaces)) => ifaces,
+        Err(StatusError::NotPresent(_)) => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+
+    let mut up = vec![];
+    for iface in ifaces {
+        // Tunnels have no carrier, so their operstate is mostly `unknown`.
+        let flags = match read_to_string(rooted("/sys/class/net").join(&iface).join("flags")) {
+            Ok(flags) => flags,
+            Err(ref err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(StatusError::Io(err)),
+        };
+        let flags = u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16)?;
+        if flags & libc::IFF_UP as u32 != 0 {
+            up.push(iface);
+        }
+    }
+    Ok(up)
+}
+
+/// The port the TCP probe of `get_ping` connects to.
+const PING_TCP_PORT: u16 = 443;
+
+/// Measure the round trip time to the provided host, e.g. `1.1.1.1` or
+/// `example.com`, with an ICMP echo request or, where unprivileged ICMP
+/// sockets are not permitted, by connecting to its HTTPS port.  Fails if
+/// there is no reply within the timeout.
+pub fn get_ping(host: &str, timeout: Duration) -> Result<Duration, StatusError> {
+    let addr = (host, PING_TCP_PORT)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| StatusError::NotPresent(format!("address of {}", host)))?;
+
+    match ping::echo(addr.ip(), timeout) {
+        Ok(rtt) => return Ok(rtt),
+        Err(ref err)
+            if matches!(
+                err.raw_os_error(),
+                Some(libc::EACCES) | Some(libc::EPERM) | Some(libc::EPROTONOSUPPORT)
+            ) => {}
+        Err(err) => return Err(StatusError::Io(err)),
+    }
+
+    let start = Instant::now();
+    match TcpStream::connect_timeout(&addr, timeout) {
+        Ok(_) => Ok(start.elapsed()),
+        // A refused connection is a reply too.
+        Err(ref err) if err.kind() == io::ErrorKind::ConnectionRefused => Ok(start.elapsed()),
+        Err(err) => Err(StatusError::Io(err)),
+    }
+}
+
+/// Fetch the public IP address from the provided HTTPS endpoint, e.g.
+/// `https://ifconfig.me/ip`, with `curl`.  The endpoint must reply with
+/// nothing but the address.
+pub fn get_public_ip(url: &str) -> Result<IpAddr, StatusError> {
+    let reply = run(Command::new("curl").args([
+        "--silent",
+        "--show-error",
+        "--fail",
+        "--max-time",
+        "30",
+        url,
+    ]))?;
+    reply
+        .trim()
+        .parse()
+        .map_err(|_| StatusError::InvalidArg(format!("{} replied {}", url, reply.trim())))
+}
+
+/// Run the provided shell command and return the first line it prints.  The
+/// command, and anything it started, is killed if it has not finished within
+/// the timeout.
+pub fn get_command(command: &str, timeout: Duration) -> Result<String, StatusError> {
+    run_shell(command, timeout)
+        .map_err(|reason| StatusError::Command(String::from(command), reason))
+}
+
+/// Run the provided shell command, see `get_command`, and return the first
+/// line it prints or the reason it failed.
+fn run_shell(command: &str, timeout: Duration) -> Result<String, String> {
+    let output = run_timeout(Command::new("sh").args(["-c", command]), timeout)?;
+    Ok(String::from(output.lines().next().unwrap_or_default()))
+}
+
+/// Run the provided command and return its output or the reason it failed.
+/// The command, and anything it started, is killed if it has not finished
+/// within the timeout.
+fn run_timeout(command: &mut Command, timeout: Duration) -> Result<String, String> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .process_group(0)
+        .spawn()
+        .map_err(|err| err.to_string())?;
+
+    // The output is read on its own thread so that a command which never
+    // closes it cannot outlast the timeout.
+    let mut stdout = child.stdout.take().ok_or("no output")?;
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut output = vec![];
+        let _ = sender.send(stdout.read_to_end(&mut output).map(|_| output));
+    });
+    let output = match receiver.recv_timeout(timeout) {
+        Ok(output) => output,
+        Err(_) => {
+            unsafe {
+                libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+            }
+            let _ = child.wait();
+            return Err(format!("timed out after {}s", timeout.as_secs_f64()));
+        }
+    };
+
+    let status = child.wait().map_err(|err| err.to_string())?;
+    if !status.success() {
+        return Err(exit_reason(status));
+    }
+    let output = output.map_err(|err| err.to_string())?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Return the brightness of the provided backlight as a percentage of its
+/// maximum brightness, e.g. `60%`.
+pub fn get_brightness(backlight: &Path) -> Result<String, StatusError> {
+    let brightness: u64 = read_number(backlight.join("brightness"))?;
+    let max_brightness: u64 = read_number(backlight.join("max_brightness"))?;
+    if max_brightness == 0 {
+        let backlight = backlight.display();
+        return Err(StatusError::InvalidArg(format!("{} has no brightness levels", backlight)));
+    }
+
+    let percentage = ((brightness as f64) / (max_brightness as f64)) * 100.0;
+    Ok(format!("{:.0}%", percentage))
+}
+
+/// ## VolumeReadout
+///
+/// The output volume.  It is displayed like `get_volume`, e.g. `45%` or
+/// `mute`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VolumeReadout {
+    /// The volume as a percentage, averaged over the channels.
+    pub percent: f64,
+    /// Whether the output is muted.
+    pub muted: bool,
+}
+
+impl std::fmt::Display for VolumeReadout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.muted {
+            write!(f, "mute")
+        } else {
+            write!(f, "{:.0}%", self.percent)
+        }
+    }
+}
+
+/// Return the volume of the provided ALSA simple mixer control, e.g. `45%`,
+/// or `mute` if it is muted.
+pub fn get_volume(control: &str) -> Result<String, StatusError> {
+    Ok(get_volume_raw(control)?.to_string())
+}
+
+/// Return the volume of the provided ALSA simple mixer control.  With the
+/// `pulse` feature the default PulseAudio or PipeWire sink is read instead
+/// and the ALSA control is a fallback for systems without a sound server.
+pub fn get_volume_raw(control: &str) -> Result<VolumeReadout, StatusError> {
+    #[cfg(feature = "pulse")]
+    return get_pactl_volume().or_else(|_| get_amixer_volume(control));
+    #[cfg(not(feature = "pulse"))]
+    return get_amixer_volume(control);
+}
+
+/// Read the volume of an ALSA simple mixer control with `amixer`.
+fn get_amixer_volume(control: &str) -> Result<VolumeReadout, StatusError> {
+    parse_amixer(&run(Command::new("amixer").args(["get", control]))?)
+        .ok_or_else(|| StatusError::NotPresent(format!("{} playback volume", control)))
+}
+
+/// Parse the volume from the output of `amixer get`.  The control is muted
+/// only if all its channels are.
+fn parse_amixer(output: &str) -> Option<VolumeReadout> {
+    // Channels are of the form "Front Left: Playback 29491 [45%] [on]".
+    let channels: Vec<(f64, bool)> = output
+        .lines()
+        .filter(|line| line.contains(": Playback"))
+        .filter_map(|line| {
+            let mut fields = line
+                .split('[')
+                .skip(1)
+                .map(|field| field.trim_end().trim_end_matches(']'));
+            let percent = fields.next()?.strip_suffix('%')?.parse().ok()?;
+            Some((percent, fields.any(|field| field == "off")))
+        })
+        .collect();
+    if channels.is_empty() {
+        return None;
+    }
+
+    let percent = channels.iter().map(|(percent, _)| percent).sum::<f64>() / channels.len() as f64;
+    let muted = channels.iter().all(|(_, muted)| *muted);
+    Some(VolumeReadout { percent, muted })
+}
+
+/// Read the volume of the default sink with `pactl`, which also talks to
+/// PipeWire through `pipewire-pulse`.
+#[cfg(feature = "pulse")]
+fn get_pactl_volume() -> Result<VolumeReadout, StatusError> {
+    let pactl = |what: &str| run(Command::new("pactl").args([what, "@DEFAULT_SINK@"]));
+
+    // The volume is of the form "Volume: front-left: 29491 /  45% / -20.81
+    // dB,   front-right: 29491 /  45% / -20.81 dB" and the mute state of the
+    // form "Mute: no".
+    let volume = pactl("get-sink-volume")?;
+    let percents: Vec<f64> = volume
+        .lines()
+        .next()
+        .unwrap_or("")
+        .split('/')
+        .filter_map(|field| field.trim().strip_suffix('%')?.parse().ok())
+        .collect();
+    if percents.is_empty() {
+        return Err(StatusError::NotPresent(String::from("default sink volume")));
+    }
+
+    Ok(VolumeReadout {
+        percent: percents.iter().sum::<f64>() / percents.len() as f64,
+        muted: pactl("get-sink-mute")?.trim() == "Mute: yes",
+    })
+}
+
+/// ## MediaReadout
+///
+/// What an MPRIS media player is playing.  It is displayed like `get_media`,
+/// e.g. `Artist – Track` or `⏸ Artist – Track` while paused.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MediaReadout {
+    /// Whether the player is playing rather than paused.
+    pub playing: bool,
+    /// The artists of the track, if known.
+    pub artist: Option<String>,
+    /// The title of the track.
+    pub title: String,
+}
+
+impl std::fmt::Display for MediaReadout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if !self.playing {
+            write!(f, "⏸ ")?;
+        }
+        match self.artist {
+            Some(ref artist) => write!(f, "{} – {}", artist, self.title),
+            None => write!(f, "{}", self.title),
+        }
+    }
+}
+
+/// Return what the active media player is playing, e.g. `Artist – Track`,
+/// truncated to the provided number of characters, or `None` if no player is
+/// playing or paused.
+pub fn get_media(width: usize) -> Result<Option<String>, StatusError> {
+    Ok(get_media_raw()?.map(|media| truncate(&media.to_string(), width)))
+}
+
+/// Return what the active media player is playing or `None` if no player is
+/// playing or paused.  The players are queried over D-Bus with `playerctl`,
+/// which prefers a playing player over a paused one.
+pub fn get_media_raw() -> Result<Option<MediaReadout>, StatusError> {
+    let output = Command::new("playerctl")
+        .args(["metadata", "--format", "{{status}}\t{{artist}}\t{{title}}"])
+        .output()
+        .map_err(|err| StatusError::Command(String::from("playerctl"), err.to_string()))?;
+    // playerctl fails if there is no player at all.
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(parse_playerctl(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse the status, artist, and title printed by `playerctl`.
+fn parse_playerctl(output: &str) -> Option<MediaReadout> {
+    let mut fields = output.lines().next()?.split('\t');
+    let playing = match fields.next()? {
+        "Playing" => true,
+        "Paused" => false,
+        _ => return None,
+    };
+    let artist = fields.next().filter(|artist| !artist.is_empty()).map(String::from);
+    let title = fields.next().filter(|title| !title.is_empty())?;
+
+    Some(MediaReadout {
+        playing,
+        artist,
+        title: String::from(title),
+    })
+}
+
+/// Shorten the text to the provided number of characters, ending it with an
+/// ellipsis if anything was cut off.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return String::from(text);
+    }
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Return the number of unread mails in the provided Maildir, i.e. of the
+/// files in its `new` directory.
+pub fn get_mail_count(maildir: &Path) -> Result<usize, StatusError> {
+    let count = maildir
+        .join("new")
+        .read_dir()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| !entry.file_name().as_bytes().starts_with(b"."))
+        .count();
+    Ok(count)
+}
+
+/// Where the latest METAR report of each weather station is published.
+#[cfg(feature = "weather")]
+const METAR_URL: &str = "https://tgftp.nws.noaa.gov/data/observations/metar/stations";
+
+/// ## WeatherReadout
+///
+/// The weather reported by a weather station.  It is displayed in Celsius
+/// with the conditions, e.g. `8°C light rain`, see `WeatherReadout::format`
+/// for other units.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeatherReadout {
+    /// The ICAO code of the weather station, e.g. `EGLL`.
+    pub station: String,
+    /// The temperature in degrees Celsius.
+    pub celsius: f64,
+    /// The weather, e.g. `light rain`, or else the cloud cover, e.g.
+    /// `overcast`, if reported.
+    pub conditions: Option<String>,
+}
+
+impl WeatherReadout {
+    /// Format the temperature in the provided unit with the conditions, e.g.
+    /// `46°F light rain`.
+    pub fn format(&self, unit: TempUnit) -> String {
+        let temp = format_temp(None, unit.degrees(self.celsius * 1000.0), unit);
+        match self.conditions {
+            Some(ref conditions) => format!("{} {}", temp, conditions),
+            None => temp,
+        }
+    }
+
+    /// Return the readout as a JSON object.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"station\":{},\"celsius\":{},\"conditions\":{}}}",
+            json_string(&self.station),
+            self.celsius,
+            self.conditions.as_ref().map_or(String::from("null"), |c| json_string(c))
+        )
+    }
+}
+
+impl std::fmt::Display for WeatherReadout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.format(TempUnit::Celsius))
+    }
+}
+
+/// Return the weather reported by the provided station, e.g. `8°C light
+/// rain`.
+#[cfg(feature = "weather")]
+pub fn get_weather(station: &str) -> Result<String, StatusError> {
+    Ok(get_weather_raw(station)?.to_string())
+}
+
+/// Fetch the latest METAR report of the weather station with the provided
+/// ICAO code, e.g. `EGLL`, with `curl`.  This may take a while if the network
+/// is slow, see `RwmStatus::set_weather` which does not wait for it.
+#[cfg(feature = "weather")]
+pub fn get_weather_raw(station: &str) -> Result<WeatherReadout, StatusError> {
+    let url = format!("{}/{}.TXT", METAR_URL, station.to_uppercase());
+    let report = run(Command::new("curl").args([
+        "--silent",
+        "--show-error",
+        "--fail",
+        "--max-time",
+        "30",
+        &url,
+    ]))?;
+    parse_metar(&report)
+        .ok_or_else(|| StatusError::NotPresent(format!("METAR report of {}", station)))
+}
+
+/// Parse the temperature and conditions of a METAR report, e.g. `EGLL
+/// 151250Z 24012KT 9999 -RA BKN012 OVC020 08/06 Q1012`.  The report may be
+/// preceded by the time it was published.
+#[cfg(feature = "weather")]
+fn parse_metar(report: &str) -> Option<WeatherReadout> {
+    let report = report.lines().rev().find(|line| !line.trim().is_empty())?;
+    let mut tokens = report.split_whitespace();
+    let station = String::from(tokens.next()?);
+
+    let mut celsius = None;
+    let mut weather = None;
+    let mut clouds: Option<&str> = None;
+    // The remarks are free-form.
+    for token in tokens.take_while(|token| *token != "RMK") {
+        if let Some((temp, _)) = token.split_once('/') {
+            celsius = celsius.or_else(|| metar_temp(temp));
+        } else if weather.is_none() {
+            weather = metar_weather(token);
+        }
+        let cover = match token.get(..3) {
+            Some("OVC") | Some("VV0") | Some("VV/") => "overcast",
+            Some("BKN") => "cloudy",
+            Some("SCT") | Some("FEW") => "partly cloudy",
+            Some("CLR") | Some("SKC") | Some("NSC") | Some("NCD") | Some("CAV") => "clear",
+            _ => continue,
+        };
+        // The cloud layers are listed from the lowest, the cover of the
+        // densest one is shown.
+        let rank = |cover| {
+            ["clear", "partly cloudy", "cloudy", "overcast"].iter().position(|c| *c == cover)
+        };
+        if clouds.is_none_or(|clouds| rank(cover) > rank(clouds)) {
+            clouds = Some(cover);
+        }
+    }
+
+    Some(WeatherReadout {
+        station,
+        celsius: celsius?,
+        conditions: weather.or(clouds.map(String::from)),
+    })
+}
+
+/// Parse a METAR temperature, e.g. `08` or `M05` for -5°C.
+#[cfg(feature = "weather")]
+fn metar_temp(temp: &str) -> Option<f64> {
+    let (sign, digits) = match temp.strip_prefix('M') {
+        Some(digits) => (-1.0, digits),
+        None => (1.0, temp),
+    };
+    if digits.len() != 2 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse::<f64>().ok().map(|temp| sign * temp)
+}
+
+/// Describe a METAR present weather group, e.g. `light rain` for `-RA`, or
+/// return `None` if the token is not one.
+#[cfg(feature = "weather")]
+fn metar_weather(token: &str) -> Option<String> {
+    let (intensity, codes) = match token.as_bytes().first() {
+        Some(b'-') => ("light ", &token[1..]),
+        Some(b'+') => ("heavy ", &token[1..]),
+        _ => ("", token),
+    };
+    // The most significant phenomena first.
+    const PHENOMENA: [(&str, &str); 12] = [
+        ("TS", "thunderstorm"),
+        ("GR", "hail"),
+        ("FZRA", "freezing rain"),
+        ("SN", "snow"),
+        ("SG", "snow"),
+        ("PL", "sleet"),
+        ("RA", "rain"),
+        ("DZ", "drizzle"),
+        ("FG", "fog"),
+        ("BR", "mist"),
+        ("HZ", "haze"),
+        ("FU", "smoke"),
+    ];
+    // Phenomena in the vicinity or recent ones are not at the station now.
+    let is_group = codes.len() % 2 == 0 && codes.bytes().all(|b| b.is_ascii_uppercase());
+    if !is_group || codes.starts_with("VC") || codes.starts_with("RE") {
+        return None;
+    }
+    PHENOMENA
+        .iter()
+        .find(|(code, _)| codes.contains(code))
+        .map(|(_, description)| format!("{}{}", intensity, description))
+}
+
+/// ## ChargeState
+///
+/// The charging status of a battery.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChargeState {
+    Full,
+    Charging,
+    Discharging,
+    Unknown,
+}
+
+impl ChargeState {
+    /// Return the character shown after the battery readout, e.g. `-` while
+    /// discharging.
+    pub fn symbol(self) -> char {
+        match self {
+            ChargeState::Full => 'F',
+            ChargeState::Charging => '+',
+            ChargeState::Discharging => '-',
+            ChargeState::Unknown => '?',
+        }
+    }
+
+    /// Return the name of the state as reported by sysfs, e.g. `Discharging`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ChargeState::Full => "Full",
+            ChargeState::Charging => "Charging",
+            ChargeState::Discharging => "Discharging",
+            ChargeState::Unknown => "Unknown",
+        }
+    }
+}
+
+impl std::fmt::Display for ChargeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+/// ## BatteryReadout
+///
+/// The readouts of a single battery.  It is displayed like `get_batt`, e.g.
+/// `63%- 1:47 -11.3W`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatteryReadout {
+    /// The remaining charge as a percentage of the design capacity.
+    pub percent: f64,
+    /// Whether the battery is charging, discharging, or full.
+    pub state: ChargeState,
+    /// The estimated time until the battery is empty while discharging or
+    /// full while charging, if it is known.
+    pub time_remaining: Option<Duration>,
+    /// The power flowing in or out of the battery in watts, if the battery
+    /// is (dis)charging and reports it.
+    pub power: Option<f64>,
+    /// The remaining energy in watt-hours, if it is known.
+    pub energy: Option<f64>,
+    /// The full charge capacity as a percentage of the design capacity,
+    /// which drops as the battery wears, if it is known.
+    pub health: Option<f64>,
+    /// The number of charge cycles, if the battery reports it.
+    pub cycles: Option<u64>,
+}
+
+impl BatteryReadout {
+    /// Format the charge percentage and status, e.g. `63%-`.
+    fn percentage_str(&self) -> String {
+        format!("{:.0}%{}", self.percent, self.state)
+    }
+
+    /// Format the time remaining, e.g. `1:47`, if it is known.
+    fn time_remaining_str(&self) -> Option<String> {
+        self.time_remaining.map(format_batt_time)
+    }
+
+    /// Format the time remaining and the power, e.g. `1:47 -11.3W`, leaving
+    /// out whichever is not known.
+    fn estimate_str(&self) -> Option<String> {
+        let power = self.power.map(|power| format_batt_power(power, self.state));
+        match (self.time_remaining_str(), power) {
+            (Some(time), Some(power)) => Some(format!("{} {}", time, power)),
+            (time, power) => time.or(power),
+        }
+    }
+
+    /// Format the remaining energy and status, e.g. `24.0Wh-`, if it is
+    /// known.
+    fn energy_str(&self) -> Option<String> {
+        self.energy.map(|energy| format!("{:.1}Wh{}", energy, self.state))
+    }
+
+    /// Format the health, e.g. `♥87%`, if it is known.
+    fn health_str(&self) -> Option<String> {
+        self.health.map(|health| format!("♥{:.0}%", health))
+    }
+
+    /// Return the readout as a JSON object.  The time remaining is in
+    /// seconds, the power in watts, and the energy in watt-hours.
+    pub fn to_json(&self) -> String {
+        fn or_null<T: ToString>(value: Option<T>) -> String {
+            value.map_or_else(|| String::from("null"), |value| value.to_string())
+        }
+
+        format!(
+            concat!(
+                "{{\"percent\":{},\"state\":{},\"time_remaining\":{},\"power\":{},",
+                "\"energy\":{},\"health\":{},\"cycles\":{}}}"
+            ),
+            self.percent,
+            json_string(self.state.name()),
+            or_null(self.time_remaining.map(|time| time.as_secs())),
+            or_null(self.power),
+            or_null(self.energy),
+            or_null(self.health),
+            or_null(self.cycles)
+        )
+    }
+}
+
+impl std::fmt::Display for BatteryReadout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.estimate_str() {
+            Some(estimate) => write!(f, "{} {}", self.percentage_str(), estimate),
+            None => write!(f, "{}", self.percentage_str()),
+        }
+    }
+}
+
+/// Return battery status for the battery at the provided path, e.g. `63%-`,
+/// followed by the estimated time remaining if it is known, e.g. `63%- 1:47`.
+pub fn get_batt(batt: &Path) -> Result<String, StatusError> {
+    Ok(get_batt_raw(batt)?.to_string())
+}
+
+/// Return the combined charge percentage and status of all the present
+/// batteries, e.g. `71%-`.  Capacities reported in µAh are converted to µWh
+/// if they have to be combined with capacities reported in µWh.  Batteries
+/// which cannot be converted are skipped.
+pub fn get_total_batt(batts: &[PathBuf]) -> Result<String, StatusError> {
+    Ok(get_total_batt_raw(batts)?.percentage_str())
+}
+
+/// Object path of UPower's composite device which combines all the
+/// batteries powering the system.
+#[cfg(feature = "upower")]
+const UPOWER_DISPLAY_DEVICE: &str = "/org/freedesktop/UPower/devices/DisplayDevice";
+
+/// Return the batteries known to UPower, each with the model of the device
+/// it is in for peripherals such as wireless mice, or `None` for the
+/// batteries powering the system.
+#[cfg(feature = "upower")]
+pub fn get_upower_batts() -> Result<Vec<(Option<String>, BatteryReadout)>, StatusError> {
+    let mut batts = vec![];
+    for device in upower(&["--enumerate"])?.lines() {
+        if device != UPOWER_DISPLAY_DEVICE {
+            batts.extend(parse_upower(&upower(&["--show-info", device])?));
+        }
+    }
+
+    if batts.is_empty() {
+        return Err(StatusError::NotPresent(String::from("UPower battery")));
+    }
+    Ok(batts)
+}
+
+/// Return the combined readout of the batteries powering the system as
+/// reported by UPower.
+#[cfg(feature = "upower")]
+pub fn get_upower_total() -> Result<BatteryReadout, StatusError> {
+    parse_upower(&upower(&["--show-info", UPOWER_DISPLAY_DEVICE])?)
+        .map(|(_, info)| info)
+        .ok_or_else(|| StatusError::NotPresent(String::from("UPower battery")))
+}
+
+/// Run the `upower` command line tool, which queries UPower over D-Bus.
+#[cfg(feature = "upower")]
+fn upower(args: &[&str]) -> Result<String, StatusError> {
+    run(Command::new("upower").args(args))
+}
+
+/// Parse the battery readout from the output of `upower --show-info`, or
+/// `None` if the device has no battery or it is not present.
+#[cfg(feature = "upower")]
+fn parse_upower(output: &str) -> Option<(Option<String>, BatteryReadout)> {
+    // Lines are of the form "  percentage:          73%" or "  time to
+    // empty:       2.7 hours".  Missing values are "N/A".
+    let mut values = HashMap::new();
+    for line in output.lines() {
+        if let Some((key, value)) = line.split_once(':') {
+            values.insert(key.trim(), value.trim());
+        }
+    }
+    let number = |key: &str| -> Option<f64> {
+        values.get(key)?.split_whitespace().next()?.trim_end_matches('%').parse().ok()
+    };
+
+    if values.get("present") == Some(&"no") {
+        return None;
+    }
+    let percent = number("percentage")?;
+
+    let state = match values.get("state").copied() {
+        Some("charging") => ChargeState::Charging,
+        Some("discharging") => ChargeState::Discharging,
+        Some("fully-charged") => ChargeState::Full,
+        _ => ChargeState::Unknown,
+    };
+    let time_key = match state {
+        ChargeState::Charging => "time to full",
+        _ => "time to empty",
+    };
+    let time_remaining = values.get(time_key).and_then(|time| {
+        let mut fields = time.split_whitespace();
+        let value: f64 = fields.next()?.parse().ok()?;
+        let secs = match fields.next()? {
+            "seconds" => value,
+            "minutes" => value * 60.0,
+            "hours" => value * 3600.0,
+            "days" => value * 86400.0,
+            _ => return None,
+        };
+        // Rounded to the minute like the times read from sysfs.
+        Some(Duration::from_secs((secs / 60.0).round() as u64 * 60))
+    });
+    let power = match state {
+        ChargeState::Charging | ChargeState::Discharging => {
+            number("energy-rate").filter(|power| *power > 0.0)
+        }
+        _ => None,
+    };
+
+    let model = match values.get("power supply") {
+        Some(&"no") => values.get("model").map(|model| String::from(*model)),
+        _ => None,
+    };
+    Some((
+        model,
+        BatteryReadout {
+            percent,
+            state,
+            time_remaining,
+            power,
+            energy: number("energy"),
+            health: number("capacity"),
+            cycles: number("charge-cycles")
+                .filter(|cycles| *cycles > 0.0)
+                .map(|cycles| cycles as u64),
+        },
+    ))
+}
+
+/// Return the estimated time until the battery at the provided path is empty
+/// while discharging or full while charging, e.g. `1:47`.
+pub fn get_batt_time(batt: &Path) -> Result<String, StatusError> {
+    read_batt_time(batt).map(format_batt_time)
+}
+
+/// Format a battery time as hours and minutes, e.g. `1:47`.
+fn format_batt_time(time: Duration) -> String {
+    let minutes = time.as_secs() / 60;
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Format the power flowing in or out of a battery, negative while
+/// discharging and positive while charging, e.g. `-9.4W` or `+31.0W`.
+fn format_batt_power(watts: f64, state: ChargeState) -> String {
+    match state {
+        ChargeState::Discharging => format!("-{:.1}W", watts),
+        ChargeState::Charging => format!("+{:.1}W", watts),
+        _ => format!("{:.1}W", watts),
+    }
+}
+
+/// Get the time for the provided timezone in the provided format.
+pub fn get_tz_time(tz_name: &str, fmt: &str) -> Result<String, StatusError> {
+    let tz: chrono_tz::Tz = tz_name.parse().map_err(StatusError::ParseTz)?;
+    let utc = Utc::now().naive_utc();
+    Ok(format!("{}", tz.from_utc_datetime(&utc).format(fmt)))
+}
+
+/// Get the local time.
+pub fn get_local_time(fmt: &str) -> String {
+    format!("{}", Local::now().format(fmt))
+}
+
+/// Return the device name, e.g. `BAT0`, of the provided sysfs path.
+fn device_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Split a device name such as `BAT10` into its base name and numeric suffix
+/// so that `BAT2` sorts before `BAT10`.
+fn natural_key(name: &str) -> (String, u64) {
+    let base = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    let index = name[base.len()..].parse().unwrap_or(0);
+    (String::from(base), index)
+}
+
+/// ## BattFormat
+///
+/// Selects how battery readouts are displayed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BattFormat {
+    /// Percentage of the design capacity, e.g. `63%-`.
+    Percentage,
+    /// Remaining energy in watt-hours, e.g. `34.2Wh-`.
+    Energy,
+}
+
+/// ## BattBackend
+///
+/// Selects where the battery readouts come from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BattBackend {
+    /// The power supplies in sysfs.
+    Sysfs,
+    /// UPower, which also knows about the batteries of peripherals such as
+    /// wireless mice.  The power supplies in sysfs are a fallback while
+    /// UPower cannot be reached.
+    #[cfg(feature = "upower")]
+    Upower,
+}
+
+/// ## BattMode
+///
+/// Selects whether several batteries are shown separately or as one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BattMode {
+    /// Each battery, e.g. `63%-|100%`.
+    PerBattery,
+    /// The charge of all the batteries as a percentage of their combined
+    /// design capacity and their overall status, e.g. `81%-`.  This suits
+    /// machines which drain one battery at a time.
+    Combined,
+}
+
+/// ## Limit
+///
+/// The value beyond which a section is coloured, see
+/// `RwmStatus::add_threshold`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Limit {
+    /// Values below the limit, e.g. a low battery.
+    Below(f64),
+    /// Values above the limit, e.g. a high temperature.
+    Above(f64),
+}
+
+impl Limit {
+    /// Return true if the value is beyond the limit.
+    pub fn crossed(self, value: f64) -> bool {
+        match self {
+            Limit::Below(limit) => value < limit,
+            Limit::Above(limit) => value > limit,
+        }
+    }
+
+    /// Return true if the value is back within the limit by at least the
+    /// provided margin, e.g. above 15% for a low battery limit of 10% and a
+    /// margin of 5.
+    pub fn recovered(self, value: f64, margin: f64) -> bool {
+        match self {
+            Limit::Below(limit) => value >= limit + margin,
+            Limit::Above(limit) => value <= limit - margin,
+        }
+    }
+}
+
+/// An alert raised once when a section crosses its limit, see
+/// `RwmStatus::add_alert`.
+struct Alert {
+    section: Section,
+    /// The mount point of a disk alert, if it only looks at one.
+    mount: Option<PathBuf>,
+    limit: Limit,
+    margin: f64,
+    message: String,
+    /// Set once the alert is raised until the value recovers.
+    raised: AtomicBool,
+}
+
+/// ## Markup
+///
+/// How colours are written into the status line text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Markup {
+    /// Plain text without colours.
+    Plain,
+    /// dwm status2d escapes, e.g. `^c#ff0000^15%^d^`.
+    Status2d,
+    /// Pango markup, e.g. `<span foreground="#ff0000">15%</span>`.
+    Pango,
+}
+
+impl Markup {
+    /// Return the text in the provided colour, if any.  Pango markup is
+    /// escaped.
+    pub fn apply(self, text: &str, color: Option<&str>) -> String {
+        match (self, color) {
+            (Markup::Plain, _) | (Markup::Status2d, None) => String::from(text),
+            (Markup::Status2d, Some(color)) => format!("^c{}^{}^d^", color, text),
+            (Markup::Pango, None) => pango_escape(text),
+            (Markup::Pango, Some(color)) => {
+                format!("<span foreground=\"{}\">{}</span>", color, pango_escape(text))
+            }
+        }
+    }
+}
+
+/// Escape the characters with a special meaning in Pango markup.
+fn pango_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// ## Section
+///
+/// A group of readouts in the status line.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Section {
+    Temperatures,
+    Fans,
+    Gpu,
+    LoadAvgs,
+    Cpu,
+    Freq,
+    Memory,
+    Swap,
+    Disks,
+    Batteries,
+    Wifi,
+    Network,
+    Vpn,
+    Ping,
+    Brightness,
+    Volume,
+    Media,
+    Mail,
+    Weather,
+    Uptime,
+    Times,
+    /// The readout of the monitor with the provided id, see
+    /// `RwmStatus::add_monitor`.
+    Monitor(String),
+}
+
+impl Section {
+    /// All the built-in sections.
+    pub const ALL: [Section; 21] = [
+        Section::Temperatures,
+        Section::Fans,
+        Section::Gpu,
+        Section::LoadAvgs,
+        Section::Cpu,
+        Section::Freq,
+        Section::Memory,
+        Section::Swap,
+        Section::Disks,
+        Section::Batteries,
+        Section::Wifi,
+        Section::Network,
+        Section::Vpn,
+        Section::Ping,
+        Section::Brightness,
+        Section::Volume,
+        Section::Media,
+        Section::Mail,
+        Section::Weather,
+        Section::Uptime,
+        Section::Times,
+    ];
+
+    /// Return the name of the section as used in templates, e.g. `temp`.
+    pub fn name(&self) -> &str {
+        match self {
+            Section::Temperatures => "temp",
+            Section::Fans => "fan",
+            Section::Gpu => "gpu",
+            Section::LoadAvgs => "load",
+            Section::Cpu => "cpu",
+            Section::Freq => "freq",
+            Section::Memory => "mem",
+            Section::Swap => "swap",
+            Section::Disks => "disk",
+            Section::Batteries => "batt",
+            Section::Wifi => "wifi",
+            Section::Network => "net",
+            Section::Vpn => "vpn",
+            Section::Ping => "ping",
+            Section::Brightness => "bright",
+            Section::Volume => "vol",
+            Section::Media => "media",
+            Section::Mail => "mail",
+            Section::Weather => "weather",
+            Section::Uptime => "uptime",
+            Section::Times => "time",
+            Section::Monitor(id) => id,
+        }
+    }
+
+    /// Return the built-in section with the provided name, see
+    /// `Section::name`.
+    pub fn from_name(name: &str) -> Option<Section> {
+        Section::ALL.iter().find(|section| section.name() == name).cloned()
+    }
+}
+
+/// ## StatusFormat
+///
+/// The layout of the status line.  The default reproduces the classic
+/// `T:... L:... B:... times` layout, other sections have to be added.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatusFormat {
+    /// Separator between sections.
+    pub separator: String,
+    /// Separator between the readouts of different devices in a section.
+    pub device_separator: String,
+    /// Sections to display, in order, with their prefixes.
+    pub sections: Vec<(Section, String)>,
+    /// Template to render the status line from instead of joining the
+    /// sections, e.g. `{temp} | {batt:percent}%{batt:state} | {time}`, see
+    /// `RwmStatus::render`.  Bars drawing separate blocks still use the
+    /// sections.
+    pub template: Option<String>,
+    /// How the colours of sections which crossed a threshold are written.
+    pub markup: Markup,
+}
+
+impl Default for StatusFormat {
+    fn default() -> StatusFormat {
+        StatusFormat {
+            separator: String::from(" "),
+            device_separator: String::from("|"),
+            sections: vec![
+                (Section::Temperatures, String::from("T:")),
+                (Section::LoadAvgs, String::from("L:")),
+                (Section::Batteries, String::from("B:")),
+                (Section::Times, String::new()),
+            ],
+            template: None,
+            markup: Markup::Plain,
+        }
+    }
+}
+
+/// ## RwmStatus
+///
+/// This struct collects device dependent paths and user settings.  It also
+/// provides convenience methods to aggregate readouts.
+pub struct RwmStatus {
+    /// The directories the devices are looked for in, see
+    /// `RwmStatus::rescan`.
+    hwmon_path: Option<PathBuf>,
+    batt_path: Option<PathBuf>,
+    backlight_path: Option<PathBuf>,
+    drm_path: PathBuf,
+    hw_mons: Vec<PathBuf>,
+    temp_unit: TempUnit,
+    temp_trend: bool,
+    temp_sensors: Vec<String>,
+    prev_temps: Mutex<HashMap<PathBuf, i64>>,
+    #[cfg(feature = "ipmi")]
+    ipmi_mons: Vec<PathBuf>,
+    /// Set if the IPMI temperatures are read with `ipmitool`, i.e. if there
+    /// are no IPMI hwmon devices but there is a BMC.
+    #[cfg(feature = "ipmi")]
+    ipmitool: bool,
+    prev_cpu: Mutex<Option<(u64, u64)>>,
+    prev_cores: Mutex<Vec<(u64, u64)>>,
+    cpu_per_core: bool,
+    cpu_usage: Mutex<Option<f64>>,
+    cpus: PathBuf,
+    freq_mode: FreqMode,
+    gpus: Vec<PathBuf>,
+    batts: Vec<PathBuf>,
+    batt_format: BattFormat,
+    batt_mode: BattMode,
+    batt_health: bool,
+    batt_backend: BattBackend,
+    charge_frames: Vec<String>,
+    charge_frame: AtomicUsize,
+    wifi_iface: Option<String>,
+    mixer: Option<String>,
+    media_width: Option<usize>,
+    maildirs: Vec<PathBuf>,
+    #[cfg(feature = "weather")]
+    weather_station: Option<String>,
+    #[cfg(feature = "weather")]
+    weather_interval: Duration,
+    #[cfg(feature = "weather")]
+    weather: Arc<Mutex<Fetched<WeatherReadout>>>,
+    net_ifaces: Vec<String>,
+    vpn_ifaces: Vec<String>,
+    vpn_up: Mutex<Option<bool>>,
+    public_ip_url: Option<String>,
+    public_ip_interval: Duration,
+    public_ip: Arc<Mutex<Fetched<IpAddr>>>,
+    ping_host: Option<String>,
+    ping_interval: Duration,
+    ping_timeout: Duration,
+    ping: Arc<Mutex<Fetched<Duration>>>,
+    prev_net: Mutex<HashMap<String, NetSample>>,
+    mounts: Vec<PathBuf>,
+    backlights: Vec<PathBuf>,
+    tzs: Vec<Tz>,
+    time_format: String,
+    format: StatusFormat,
+    placeholder: String,
+    /// The last error of each failed readout, see `RwmStatus::failed`.
+    failures: Mutex<HashMap<String, String>>,
+    monitors: Vec<Box<dyn Monitor>>,
+    thresholds: Vec<(Section, Limit, String)>,
+    disk_thresholds: Vec<(PathBuf, Limit, String)>,
+    alerts: Vec<Alert>,
+    intervals: Vec<(Section, Duration)>,
+    readouts: Mutex<HashMap<String, (Instant, Option<String>)>>,
+}
+
+/// A readout fetched over the network in the background, see
+/// `fetch_in_background`.
+struct Fetched<T> {
+    /// The last successful readout.
+    readout: Option<T>,
+    /// The error of the last fetch until it is logged.
+    error: Option<StatusError>,
+    /// When the last fetch finished and whether it succeeded.
+    fetched: Option<(Instant, bool)>,
+    fetching: bool,
+}
+
+impl<T> Default for Fetched<T> {
+    fn default() -> Fetched<T> {
+        Fetched {
+            readout: None,
+            error: None,
+            fetched: None,
+            fetching: false,
+        }
+    }
+}
+
+/// How long to wait before fetching a readout again after a failure, e.g.
+/// while the network is not up yet.
+const FETCH_RETRY: Duration = Duration::from_secs(5 * 60);
+
+/// Return the readout fetched most recently and the error of the last fetch,
+/// if it has not been returned yet.  Once the readout is due, i.e. its
+/// interval has passed, it is fetched again in the background so that a slow
+/// or failing network never holds up the status line.  Failed fetches keep
+/// the previous readout and are retried after a few minutes at most.
+fn fetch_in_background<T, F>(
+    cache: &Arc<Mutex<Fetched<T>>>,
+    interval: Duration,
+    fetch: F,
+) -> (Option<T>, Option<StatusError>)
+where
+    T: Clone + Send + 'static,
+    F: FnOnce() -> Result<T, StatusError> + Send + 'static,
+{
+    let mut fetched = cache.lock().unwrap();
+    let due = match fetched.fetched {
+        Some((fetched_at, true)) => fetched_at.elapsed() >= interval,
+        Some((fetched_at, false)) => fetched_at.elapsed() >= interval.min(FETCH_RETRY),
+        None => true,
+    };
+    if due && !fetched.fetching {
+        fetched.fetching = true;
+        let cache = Arc::clone(cache);
+        thread::spawn(move || {
+            let result = fetch();
+            let mut fetched = cache.lock().unwrap();
+            fetched.fetched = Some((Instant::now(), result.is_ok()));
+            match result {
+                Ok(readout) => fetched.readout = Some(readout),
+                Err(err) => fetched.error = Some(err),
+            }
+            fetched.fetching = false;
+        });
+    }
+    (fetched.readout.clone(), fetched.error.take())
+}
+
+/// The interfaces of a configured network interface, their total `(rx, tx)`
+/// byte counts, and when they were read.
+type NetSample = (Vec<String>, (u64, u64), Instant);
+
+/// ## Tz
+///
+/// Holds the label, name, and time format of a time zone.
+struct Tz {
+    label: char,
+    name: String,
+    format: String,
+}
+
+/// ## RwmStatusBuilder
+///
+/// Builds an `RwmStatus` from only the devices, time zones, and monitors it
+/// is given, e.g. `RwmStatusBuilder::new().with_tz('U', "UTC").build()`.
+/// Sections whose devices are not looked for have no readouts.
+#[derive(Default)]
+pub struct RwmStatusBuilder {
+    hwmon_path: Option<PathBuf>,
+    batt_path: Option<PathBuf>,
+    backlight_path: Option<PathBuf>,
+    tzs: Vec<Tz>,
+    monitors: Vec<Box<dyn Monitor>>,
+}
+
+impl RwmStatusBuilder {
+    /// Start a new builder.  It looks for no devices and has no time zones
+    /// or monitors until they are added with the `with_` methods.
+    pub fn new() -> RwmStatusBuilder {
+        RwmStatusBuilder::default()
+    }
+
+    /// Look for the temperature sensors in the monitors found in the provided
+    /// directory, e.g. `/sys/class/hwmon`.
+    pub fn with_hwmon_path<P: AsRef<Path>>(mut self, hwmon_path: P) -> RwmStatusBuilder {
+        self.hwmon_path = Some(hwmon_path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Look for the batteries in the provided directory, e.g.
+    /// `/sys/class/power_supply`.
+    pub fn with_batteries<P: AsRef<Path>>(mut self, batt_path: P) -> RwmStatusBuilder {
+        self.batt_path = Some(batt_path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Look for the backlights in the provided directory, e.g.
+    /// `/sys/class/backlight`.
+    pub fn with_backlights<P: AsRef<Path>>(mut self, backlight_path: P) -> RwmStatusBuilder {
+        self.backlight_path = Some(backlight_path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Show the time in the time zone with the provided name, e.g. `UTC`,
+    /// labelled with the provided character.
+    pub fn with_tz(self, label: char, name: &str) -> RwmStatusBuilder {
+        self.with_tz_format(label, name, "%H:%M")
+    }
+
+    /// Show the time in the provided time zone with the provided `chrono`
+    /// format.
+    pub fn with_tz_format(mut self, label: char, name: &str, format: &str) -> RwmStatusBuilder {
+        self.tzs.push(Tz {
+            label,
+            name: String::from(name),
+            format: String::from(format),
+        });
+        self
+    }
+
+    /// Add a monitor, see `RwmStatus::add_monitor`.
+    pub fn with_monitor(mut self, monitor: Box<dyn Monitor>) -> RwmStatusBuilder {
+        self.monitors.push(monitor);
+        self
+    }
+
+    /// Build the `RwmStatus`, which collects the paths of the devices found in
+    /// the provided directories for later use.
+    pub fn build(self) -> RwmStatus {
+        let mut rwmstatus = RwmStatus {
+            hwmon_path: self.hwmon_path,
+            batt_path: self.batt_path,
+            backlight_path: self.backlight_path,
+            drm_path: rooted("/sys/class/drm"),
+            hw_mons: vec![],
+            temp_unit: TempUnit::Celsius,
+            temp_trend: false,
+            temp_sensors: vec![],
+            prev_temps: Mutex::new(HashMap::new()),
+            #[cfg(feature = "ipmi")]
+            ipmi_mons: vec![],
+            #[cfg(feature = "ipmi")]
+            ipmitool: false,
+            prev_cpu: Mutex::new(None),
+            prev_cores: Mutex::new(vec![]),
+            cpu_per_core: false,
+            cpu_usage: Mutex::new(None),
+            cpus: rooted("/sys/devices/system/cpu"),
+            freq_mode: FreqMode::Max,
+            gpus: vec![],
+            batts: vec![],
+            batt_format: BattFormat::Percentage,
+            batt_mode: BattMode::PerBattery,
+            batt_health: false,
+            batt_backend: BattBackend::Sysfs,
+            charge_frames: vec![],
+            charge_frame: AtomicUsize::new(0),
+            wifi_iface: None,
+            mixer: None,
+            media_width: None,
+            maildirs: vec![],
+            #[cfg(feature = "weather")]
+            weather_station: None,
+            #[cfg(feature = "weather")]
+            weather_interval: Duration::from_secs(30 * 60),
+            #[cfg(feature = "weather")]
+            weather: Arc::new(Mutex::new(Fetched::default())),
+            net_ifaces: vec![],
+            vpn_ifaces: vec![],
+            vpn_up: Mutex::new(None),
+            public_ip_url: None,
+            public_ip_interval: Duration::from_secs(10 * 60),
+            public_ip: Arc::new(Mutex::new(Fetched::default())),
+            ping_host: None,
+            ping_interval: Duration::from_secs(10),
+            ping_timeout: Duration::from_secs(2),
+            ping: Arc::new(Mutex::new(Fetched::default())),
+            prev_net: Mutex::new(HashMap::new()),
+            mounts: vec![],
+            backlights: vec![],
+            tzs: self.tzs,
+            time_format: String::from("KW %W %a %d %b %H:%M %Z %Y"),
+            format: StatusFormat::default(),
+            placeholder: String::new(),
+            failures: Mutex::new(HashMap::new()),
+            monitors: self.monitors,
+            thresholds: vec![],
+            disk_thresholds: vec![],
+            alerts: vec![],
+            intervals: vec![],
+            readouts: Mutex::new(HashMap::new()),
+        };
+        rwmstatus.scan_devices();
+        rwmstatus
+    }
+}
+
+impl RwmStatus {
+    /// Build a new RwmStatus object.  This function collects all the monitor,
+    /// battery, and backlight paths, found in `hwmon_path`, `batt_path`, and
+    /// `backlight_path` respectively, for later use.  Each time zone is given
+    /// as its label, name, and an optional `chrono` format, `%H:%M` if `None`.
+    /// See `RwmStatusBuilder` to only look for some of the devices.
+    pub fn new(
+        hwmon_path: &Path,
+        batt_path: &Path,
+        backlight_path: &Path,
+        tzs: &[(char, &str, Option<&str>)],
+    ) -> RwmStatus {
+        let mut builder = RwmStatusBuilder::new()
+            .with_hwmon_path(hwmon_path)
+            .with_batteries(batt_path)
+            .with_backlights(backlight_path);
+        for (label, name, format) in tzs {
+            builder = builder.with_tz_format(*label, name, format.unwrap_or("%H:%M"));
+        }
+        builder.build()
+    }
+
+    /// Set the `chrono` format of the local time shown after the time zones.
+    pub fn set_time_format(&mut self, time_format: &str) {
+        self.time_format = String::from(time_format);
+    }
+
+    /// Set the unit in which temperatures are displayed.
+    pub fn set_temp_unit(&mut self, temp_unit: TempUnit) {
+        self.temp_unit = temp_unit;
+    }
+
+    /// Annotate each temperature with a glyph showing whether it rose (`↑`),
+    /// fell (`↓`), or stayed the same (`→`) since the previous readout.
+    pub fn set_temp_trend(&mut self, temp_trend: bool) {
+        self.temp_trend = temp_trend;
+    }
+
+    /// Only show the temperature sensors selected by any of the provided
+    /// patterns, all of them if there are none.  A pattern selects sensors by
+    /// their label, e.g. `Package id 0`, or by their hwmon device name and
+    /// label, e.g. `nvme:Composite`.  `*` matches any sequence of characters
+    /// and `?` any single character, e.g. `coretemp:*`.
+    pub fn set_temp_sensors(&mut self, patterns: &[&str]) {
+        self.temp_sensors = patterns.iter().map(|pattern| String::from(*pattern)).collect();
+    }
+
+    /// Set the format of the battery readouts.
+    pub fn set_batt_format(&mut self, batt_format: BattFormat) {
+        self.batt_format = batt_format;
+    }
+
+    /// Set whether several batteries are shown separately or combined.
+    pub fn set_batt_mode(&mut self, batt_mode: BattMode) {
+        self.batt_mode = batt_mode;
+    }
+
+    /// Show the health of each battery after its readout, e.g. `♥87%`.
+    pub fn set_batt_health(&mut self, batt_health: bool) {
+        self.batt_health = batt_health;
+    }
+
+    /// Set where the battery readouts come from.
+    pub fn set_batt_backend(&mut self, batt_backend: BattBackend) {
+        self.batt_backend = batt_backend;
+    }
+
+    /// Animate charging batteries by cycling through the provided frames, one
+    /// frame per readout, in place of the `+` status.  An empty slice turns
+    /// the animation off.
+    pub fn set_charge_animation(&mut self, frames: &[&str]) {
+        self.charge_frames = frames.iter().map(|frame| String::from(*frame)).collect();
+        self.charge_frame.store(0, atomic::Ordering::Relaxed);
+    }
+
+    /// Also show the usage of each core, e.g. `37% 12%|80%|5%|40%`.
+    pub fn set_cpu_per_core(&mut self, per_core: bool) {
+        self.cpu_per_core = per_core;
+    }
+
+    /// Set the directory with the CPUs, `/sys/devices/system/cpu` by
+    /// default, and how their frequencies are combined.
+    pub fn set_cpu_freq(&mut self, cpus: &Path, mode: FreqMode) {
+        self.cpus = PathBuf::from(cpus);
+        self.freq_mode = mode;
+    }
+
+    /// Set the directory with the DRM cards of the GPUs, `/sys/class/drm` by
+    /// default.
+    pub fn set_drm_path(&mut self, drm_path: &Path) {
+        self.drm_path = PathBuf::from(drm_path);
+        self.gpus = RwmStatus::get_cards(drm_path);
+    }
+
+    /// Set the wireless interface to report on, `None` to not report any.
+    pub fn set_wifi_iface(&mut self, iface: Option<&str>) {
+        self.wifi_iface = iface.map(String::from);
+    }
+
+    /// Set the ALSA simple mixer control to report the volume of, e.g.
+    /// `Master`, `None` to not report the volume.
+    pub fn set_mixer(&mut self, control: Option<&str>) {
+        self.mixer = control.map(String::from);
+    }
+
+    /// Show what the active media player is playing, truncated to the
+    /// provided number of characters, `None` to not show it.
+    pub fn set_media_width(&mut self, width: Option<usize>) {
+        self.media_width = width;
+    }
+
+    /// Set the Maildirs to count the unread mails of.
+    pub fn set_maildirs(&mut self, maildirs: &[&str]) {
+        self.maildirs = maildirs.iter().map(PathBuf::from).collect();
+    }
+
+    /// Show the weather reported by the weather station with the provided
+    /// ICAO code, e.g. `EGLL`, fetched once per interval, `None` to not show
+    /// it.
+    #[cfg(feature = "weather")]
+    pub fn set_weather(&mut self, station: Option<&str>, interval: Duration) {
+        self.weather_station = station.map(String::from);
+        self.weather_interval = interval;
+        self.weather = Arc::new(Mutex::new(Fetched::default()));
+    }
+
+    /// Return the Maildirs to count the unread mails of.  Their `new`
+    /// directories may be watched to update the mail section as soon as mail
+    /// arrives, see `RwmStatus::invalidate`.
+    pub fn maildirs(&self) -> &[PathBuf] {
+        &self.maildirs
+    }
+
+    /// Set the network interfaces to report the throughput of.  `default`
+    /// follows the interface of the default route and a pattern with `*` or
+    /// `?` wildcards, e.g. `wl*`, adds up the traffic of all the matching
+    /// interfaces except for the loopback.
+    pub fn set_net_ifaces(&mut self, ifaces: &[&str]) {
+        self.net_ifaces = ifaces.iter().map(|iface| String::from(*iface)).collect();
+        self.prev_net.lock().unwrap().clear();
+    }
+
+    /// Set the VPN interfaces to report on, e.g. `wg0`, or patterns with `*`
+    /// or `?` wildcards, e.g. `tun*`.
+    pub fn set_vpn_ifaces(&mut self, ifaces: &[&str]) {
+        self.vpn_ifaces = ifaces.iter().map(|iface| String::from(*iface)).collect();
+        *self.vpn_up.lock().unwrap() = None;
+    }
+
+    /// Show the public IP address fetched from the provided HTTPS endpoint
+    /// once per interval, see `get_public_ip`, `None` to not show it.  It is
+    /// also fetched again as soon as a VPN interface goes up or down.
+    pub fn set_public_ip(&mut self, url: Option<&str>, interval: Duration) {
+        self.public_ip_url = url.map(String::from);
+        self.public_ip_interval = interval;
+        self.public_ip = Arc::new(Mutex::new(Fetched::default()));
+    }
+
+    /// Measure the round trip time to the provided host once per interval,
+    /// see `get_ping`, `None` to not measure it.  Probes without a reply
+    /// within the timeout fail.
+    pub fn set_ping(&mut self, host: Option<&str>, interval: Duration, timeout: Duration) {
+        self.ping_host = host.map(String::from);
+        self.ping_interval = interval;
+        self.ping_timeout = timeout;
+        self.ping = Arc::new(Mutex::new(Fetched::default()));
+    }
+
+    /// Set the mount points to report the disk usage of.
+    pub fn set_mounts(&mut self, mounts: &[&str]) {
+        self.mounts = mounts.iter().map(PathBuf::from).collect();
+    }
+
+    /// Colour the provided section, e.g. `#ff0000`, while its value is beyond
+    /// the limit, see `RwmStatus::get_value`.  If several limits are crossed
+    /// the colour of the last one added is used.
+    pub fn add_threshold(&mut self, section: Section, limit: Limit, color: &str) {
+        self.thresholds.push((section, limit, String::from(color)));
+    }
+
+    /// Colour the disk section while the free space of the file system
+    /// mounted at the provided path, as a percentage, is beyond the limit.
+    /// These colours take precedence over the thresholds of the whole
+    /// section.
+    pub fn add_disk_threshold(&mut self, mount: &str, limit: Limit, color: &str) {
+        self.disk_thresholds.push((PathBuf::from(mount), limit, String::from(color)));
+    }
+
+    /// Raise an alert with the provided message once the value of the
+    /// section crosses the limit, see `RwmStatus::take_alerts`.  It is only
+    /// raised again after the value is back within the limit by the margin,
+    /// so that a value hovering around the limit does not raise it on every
+    /// update.  A battery is only below its limit while discharging.
+    pub fn add_alert(&mut self, section: Section, limit: Limit, margin: f64, message: &str) {
+        self.alerts.push(Alert {
+            section,
+            mount: None,
+            limit,
+            margin,
+            message: String::from(message),
+            raised: AtomicBool::new(false),
+        });
+    }
+
+    /// Raise an alert once the free space of the file system mounted at the
+    /// provided path, as a percentage, crosses the limit, see
+    /// `RwmStatus::add_alert`.
+    pub fn add_disk_alert(&mut self, mount: &str, limit: Limit, margin: f64, message: &str) {
+        self.alerts.push(Alert {
+            section: Section::Disks,
+            mount: Some(PathBuf::from(mount)),
+            limit,
+            margin,
+            message: String::from(message),
+            raised: AtomicBool::new(false),
+        });
+    }
+
+    /// Read the provided section at most once per interval, its previous
+    /// readouts are reused by `RwmStatus::collect_all` and
+    /// `RwmStatus::snapshot` in between.  Sections without an interval are
+    /// read every time.  This overrides the interval of a monitor, see
+    /// `Monitor::interval`.
+    pub fn set_interval(&mut self, section: Section, interval: Duration) {
+        self.intervals.retain(|(known, _)| *known != section);
+        self.intervals.push((section, interval));
+    }
+
+    /// Set the layout of the status line.
+    pub fn set_format(&mut self, format: StatusFormat) {
+        self.format = format;
+    }
+
+    /// Show the provided placeholder in place of failed readouts, e.g. `n/a`.
+    /// It is empty by default.
+    pub fn set_placeholder(&mut self, placeholder: &str) {
+        self.placeholder = String::from(placeholder);
+    }
+
+    /// Add a monitor.  Its readout is shown where its id is used as a section
+    /// in the layout, see `Section::Monitor`, or as a template placeholder.
+    pub fn add_monitor(&mut self, monitor: Box<dyn Monitor>) {
+        self.monitors.push(monitor);
+    }
+
+    /// Return the layout of the status line.
+    pub fn format(&self) -> &StatusFormat {
+        &self.format
+    }
+
+    /// Collect all the paths of the form base_path/prefix*
+    fn get_paths(base_path: &Path, prefix: &str) -> Vec<PathBuf> {
+        let dir = match base_path.read_dir() {
+            Ok(iter) => iter,
+            Err(_) => return vec![],
+        };
+
+        let dir_contents = dir.filter_map(|path_result| {
+            path_result.ok().map(|path| path.path())
+        });
+
+        let mut paths: Vec<PathBuf> = dir_contents
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|os_str| os_str.to_str())
+                    .filter(|entry| entry.starts_with(prefix))
+                    .is_some()
+            })
+            .collect();
+
+        paths.sort_unstable_by_key(|path| natural_key(&device_name(path)));
+        paths
+    }
+
+    /// Look for the devices again and forget their static attributes, see
+    /// `forget_static_attributes`, e.g. after a battery has been plugged in.
+    /// Unlike building a new status this keeps the samples taken so far,
+    /// such as those of the CPU usage and the network rates, and the state of
+    /// the alerts.
+    pub fn rescan(&mut self) {
+        forget_static_attributes();
+        self.scan_devices();
+    }
+
+    /// Collect the devices found in the configured directories.
+    fn scan_devices(&mut self) {
+        self.hw_mons = self.hwmon_path.as_deref().map(temp_devices).unwrap_or_default();
+        #[cfg(feature = "ipmi")]
+        {
+            // The IPMI devices are read on their own, also if they are in the
+            // hwmon path.
+            let is_ipmi = |hwmon: &PathBuf| {
+                read_to_string(hwmon.join("name")).is_ok_and(|name| name.contains("ipmi"))
+            };
+            self.hw_mons.retain(|hwmon| !is_ipmi(hwmon));
+            self.ipmi_mons = RwmStatus::get_paths(&rooted("/sys/class/hwmon"), "hwmon");
+            self.ipmi_mons.retain(is_ipmi);
+            self.ipmitool = self.ipmi_mons.is_empty() && has_ipmitool();
+        }
+        self.gpus = RwmStatus::get_cards(&self.drm_path);
+        self.batts = self.batt_path.as_deref().map(batt_devices).unwrap_or_default();
+        self.backlights = self
+            .backlight_path
+            .as_deref()
+            .map(|backlight_path| RwmStatus::get_paths(backlight_path, ""))
+            .unwrap_or_default();
+    }
+
+    /// Collect the DRM cards in the provided directory, leaving out their
+    /// connectors such as `card0-DP-1`.
+    fn get_cards(drm_path: &Path) -> Vec<PathBuf> {
+        RwmStatus::get_paths(drm_path, "card")
+            .into_iter()
+            .filter(|card| !device_name(card).contains('-'))
+            .collect()
+    }
+
+    /// Read the selected sensors of the provided monitor, see
+    /// `RwmStatus::set_temp_sensors`.
+    fn read_temps(&self, hw_mon: &Path) -> Result<Vec<TempReadout>, StatusError> {
+        let mut temps = get_temp_raw(hw_mon)?;
+        if !self.temp_sensors.is_empty() {
+            temps.retain(|temp| self.temp_sensors.iter().any(|pattern| temp.matches(pattern)));
+        }
+        Ok(temps)
+    }
+
+    /// Return temperature reads from all monitors.
+    pub fn get_temperatures(&self) -> Option<String> {
+        #[allow(unused_mut)]
+        let mut temp_strs: Vec<String> = self.hw_mons
+            .iter()
+            .filter_map(|hw_mon| match self.read_temps(hw_mon) {
+                Ok(ref temps) if temps.is_empty() => None,
+                Ok(temps) => {
+                    let temp_strs: Vec<String> = temps
+                        .iter()
+                        .map(|temp| {
+                            let degrees = self.temp_unit.degrees(temp.millidegrees);
+                            format!(
+                                "{}{}",
+                                format_temp(temp.label.as_ref(), degrees, self.temp_unit),
+                                self.temp_trend(&temp.input, degrees)
+                            )
+                        })
+                        .collect();
+                    Some(temp_strs.join(&self.format.device_separator))
+                }
+                Err(err) => Some(self.failed("temp", &hw_mon.display(), err)),
+            })
+            .collect();
+
+        #[cfg(feature = "ipmi")]
+        temp_strs.extend(self.get_ipmi_temperatures());
+
+        if temp_strs.is_empty() {
+            return None;
+        }
+        Some(temp_strs.join(&self.format.device_separator))
+    }
+
+    /// Return the temperatures of each IPMI hwmon device or, if there are
+    /// none, of the BMC read with `ipmitool`.
+    #[cfg(feature = "ipmi")]
+    fn get_ipmi_temperatures(&self) -> Vec<String> {
+        if self.ipmitool {
+            let temps = get_ipmi_temps(&[], self.temp_unit)
+                .unwrap_or_else(|err| self.failed("ipmi", &"ipmitool", err));
+            return vec![temps];
+        }
+
+        self.ipmi_mons
+            .iter()
+            .map(|hw_mon| {
+                get_ipmi_temps(std::slice::from_ref(hw_mon), self.temp_unit)
+                    .unwrap_or_else(|err| self.failed("ipmi", &hw_mon.display(), err))
+            })
+            .collect()
+    }
+
+    /// Return the speeds of the fans of all monitors or `None` if there are
+    /// no fans.
+    pub fn get_fans(&self) -> Option<String> {
+        let fan_strs: Vec<String> = self.hw_mons
+            .iter()
+            .filter_map(|hw_mon| match get_fans_raw(hw_mon) {
+                Ok(fans) => {
+                    let fan_strs: Vec<String> = fans.iter().map(|fan| fan.to_string()).collect();
+                    Some(fan_strs.join(&self.format.device_separator))
+                }
+                // Most monitors have no fans at all.
+                Err(StatusError::NotPresent(_)) => None,
+                Err(err) => Some(self.failed("fan", &hw_mon.display(), err)),
+            })
+            .collect();
+
+        if fan_strs.is_empty() {
+            return None;
+        }
+        Some(fan_strs.join(&self.format.device_separator))
+    }
+
+    /// Read each GPU with its source for error messages.  DRM cards without
+    /// any readouts, such as those of the proprietary NVIDIA driver, are left
+    /// out.
+    fn read_gpus_raw(&self) -> Vec<(String, Result<GpuReadout, StatusError>)> {
+        #[allow(unused_mut)]
+        let mut gpus: Vec<(String, Result<GpuReadout, StatusError>)> = self.gpus
+            .iter()
+            .map(|card| (card.display().to_string(), get_gpu_raw(card)))
+            .filter(|(_, gpu)| !matches!(gpu, Err(StatusError::NotPresent(_))))
+            .collect();
+
+        #[cfg(feature = "nvml")]
+        match get_nvml_gpus() {
+            Ok(nvml_gpus) => {
+                gpus.extend(nvml_gpus.into_iter().map(|gpu| (String::from("NVML"), Ok(gpu))))
+            }
+            // There is no NVIDIA driver.
+            Err(StatusError::NotPresent(_)) => {}
+            Err(err) => gpus.push((String::from("NVML"), Err(err))),
+        }
+
+        gpus
+    }
+
+    /// Return the temperature and utilization of all GPUs, e.g. `67°C 45%`,
+    /// or `None` if there are no GPUs.
+    pub fn get_gpus(&self) -> Option<String> {
+        let gpu_strs: Vec<String> = self
+            .read_gpus_raw()
+            .into_iter()
+            .map(|(source, gpu)| match gpu {
+                Ok(gpu) => gpu.format(self.temp_unit),
+                Err(err) => self.failed("gpu", &source, err),
+            })
+            .collect();
+
+        if gpu_strs.is_empty() {
+            return None;
+        }
+        Some(gpu_strs.join(&self.format.device_separator))
+    }
+
+    /// Return the trend glyph of the sensor and remember the temperature for
+    /// the next readout.
+    fn temp_trend(&self, sensor: &Path, temp: i64) -> &'static str {
+        if !self.temp_trend {
+            return "";
+        }
+
+        let prev = self.prev_temps.lock().unwrap().insert(sensor.to_path_buf(), temp);
+        match prev.map(|prev| temp.cmp(&prev)) {
+            Some(Ordering::Greater) => "↑",
+            Some(Ordering::Less) => "↓",
+            _ => "→",
+        }
+    }
+
+    /// Return the three load average values.
+    #[inline]
+    pub fn get_load_avgs(&self) -> String {
+        get_load_avgs().unwrap_or_else(|err| self.failed("load", &"/proc/loadavg", err))
+    }
+
+    /// Return the CPU usage since the previous call, e.g. `37%`, followed by
+    /// the usage of each core if enabled, see `RwmStatus::set_cpu_per_core`.
+    /// There is nothing to compare against on the first call which returns
+    /// `--%`.
+    pub fn get_cpu_usage(&self) -> String {
+        let curr = match get_cpu_times() {
+            Ok(curr) => curr,
+            Err(err) => return self.failed("cpu", &"/proc/stat", err),
+        };
+
+        let prev = self.prev_cpu.lock().unwrap().replace(curr);
+        let usage = prev.and_then(|prev| cpu_usage(prev, curr));
+        *self.cpu_usage.lock().unwrap() = usage;
+        let usage_str = format_cpu_usage(usage);
+        if !self.cpu_per_core {
+            return usage_str;
+        }
+
+        let curr = match get_core_times() {
+            Ok(curr) => curr,
+            Err(err) => return self.failed("cpu", &"/proc/stat", err),
+        };
+
+        let prev = mem::replace(&mut *self.prev_cores.lock().unwrap(), curr.clone());
+        let core_strs: Vec<String> = curr
+            .iter()
+            .enumerate()
+            .map(|(idx, curr)| {
+                format_cpu_usage(prev.get(idx).and_then(|prev| cpu_usage(*prev, *curr)))
+            })
+            .collect();
+        format!("{} {}", usage_str, core_strs.join(&self.format.device_separator))
+    }
+
+    /// Return the current CPU frequency, e.g. `2.4GHz`, or `None` if the
+    /// CPUs do not support cpufreq, e.g. in a virtual machine.
+    pub fn get_cpu_freq(&self) -> Option<String> {
+        match get_cpu_freq(&self.cpus, self.freq_mode) {
+            Ok(freq) => Some(freq),
+            Err(StatusError::NotPresent(_)) => None,
+            Err(err) => Some(self.failed("freq", &self.cpus.display(), err)),
+        }
+    }
+
+    /// Return the used and total memory.
+    #[inline]
+    pub fn get_memory(&self) -> String {
+        get_mem().unwrap_or_else(|err| self.failed("memory", &"/proc/meminfo", err))
+    }
+
+    /// Return the used swap space, e.g. `0.3G`, or `None` if there is no
+    /// swap.
+    pub fn get_swap(&self) -> Option<String> {
+        match get_mem_raw() {
+            Ok(mem) if mem.swap_total == 0 => None,
+            Ok(mem) => Some(human_size(mem.swap_used)),
+            Err(err) => Some(self.failed("swap", &"/proc/meminfo", err)),
+        }
+    }
+
+    /// Return the available space of all the configured mount points or
+    /// `None` if there are none.
+    pub fn get_disks(&self) -> Option<String> {
+        if self.mounts.is_empty() {
+            return None;
+        }
+
+        let disk_strs: Vec<String> = self.mounts
+            .iter()
+            .map(|mount| {
+                get_disk(mount).unwrap_or_else(|err| self.failed("disk", &mount.display(), err))
+            })
+            .collect();
+        Some(disk_strs.join(&self.format.device_separator))
+    }
+
+    /// Return battery status for all batteries, or for all of them combined
+    /// depending on the battery mode.
+    pub fn get_batteries(&self) -> Option<String> {
+        if self.batt_mode == BattMode::Combined {
+            return self.get_total_battery();
+        }
+
+        #[cfg(feature = "upower")]
+        if self.batt_backend == BattBackend::Upower {
+            match get_upower_batts() {
+                Ok(batts) => {
+                    let frame = self.next_charge_frame();
+                    let batt_strs: Vec<String> = batts
+                        .iter()
+                        .map(|(model, info)| {
+                            let source = model.as_deref().unwrap_or("UPower");
+                            let readout = self.batt_readout_str(info)
+                                .map(|readout| self.format_batt(info, readout, frame))
+                                .unwrap_or_else(|err| self.failed("battery", &source, err));
+                            match model {
+                                Some(model) => format!("{}:{}", model, readout),
+                                None => readout,
+                            }
+                        })
+                        .collect();
+                    return Some(batt_strs.join(&self.format.device_separator));
+                }
+                Err(err) => {
+                    self.failed("battery", &"UPower", err);
+                }
+            }
+        }
+
+        if self.batts.is_empty() {
+            return None;
+        }
+
+        let frame = self.next_charge_frame();
+        let batt_strs: Vec<String> = self.batts
+            .iter()
+            .map(|batt| {
+                self.read_batt(batt, frame)
+                    .unwrap_or_else(|err| self.failed("battery", &batt.display(), err))
+            })
+            .collect();
+        Some(batt_strs.join(&self.format.device_separator))
+    }
+
+    /// Return the combined readout of all batteries in the configured
+    /// format, see `RwmStatus::read_batt`.
+    pub fn get_total_battery(&self) -> Option<String> {
+        #[cfg(feature = "upower")]
+        if self.batt_backend == BattBackend::Upower {
+            match get_upower_total() {
+                Ok(info) => {
+                    let frame = self.next_charge_frame();
+                    let readout = self.batt_readout_str(&info)
+                        .map(|readout| self.format_batt(&info, readout, frame));
+                    return Some(
+                        readout.unwrap_or_else(|err| self.failed("battery", &"UPower", err)),
+                    );
+                }
+                Err(err) => {
+                    self.failed("battery", &"UPower", err);
+                }
+            }
+        }
+
+        if self.batts.is_empty() {
+            return None;
+        }
+
+        let frame = self.next_charge_frame();
+        let readout = get_total_batt_raw(&self.batts).and_then(|info| {
+            let readout = self.batt_readout_str(&info)?;
+            Ok(self.format_batt(&info, readout, frame))
+        });
+        Some(readout.unwrap_or_else(|err| {
+            let names: Vec<String> = self.batts.iter().map(|batt| device_name(batt)).collect();
+            self.failed("battery", &names.join(", "), err)
+        }))
+    }
+
+    /// Return the battery readout in the configured format followed by the
+    /// estimated time remaining, the power, and the health if enabled.  The
+    /// `+` status of a charging battery is
+    /// replaced by the animation frame if one is provided.
+    fn read_batt(&self, batt: &Path, frame: Option<&str>) -> Result<String, StatusError> {
+        let info = get_batt_raw(batt)?;
+        let readout = match self.batt_format {
+            BattFormat::Percentage => info.percentage_str(),
+            BattFormat::Energy => get_batt_energy(batt)?,
+        };
+        Ok(self.format_batt(&info, readout, frame))
+    }
+
+    /// Return the charge of the provided readout in the configured format,
+    /// e.g. `63%-` or `24.0Wh-`.
+    fn batt_readout_str(&self, info: &BatteryReadout) -> Result<String, StatusError> {
+        match self.batt_format {
+            BattFormat::Percentage => Ok(info.percentage_str()),
+            BattFormat::Energy => info
+                .energy_str()
+                .ok_or_else(|| StatusError::NotPresent(String::from("battery energy"))),
+        }
+    }
+
+    /// Follow the provided battery readout with the estimated time remaining,
+    /// the power, and the health if enabled, see `RwmStatus::read_batt`.
+    fn format_batt(&self, info: &BatteryReadout, readout: String, frame: Option<&str>) -> String {
+        let readout = match frame {
+            Some(frame) if readout.ends_with('+') => {
+                format!("{}{}", readout.trim_end_matches('+'), frame)
+            }
+            _ => readout,
+        };
+
+        let readout = match info.estimate_str() {
+            Some(estimate) => format!("{} {}", readout, estimate),
+            None => readout,
+        };
+
+        match info.health_str() {
+            Some(health) if self.batt_health => format!("{} {}", readout, health),
+            _ => readout,
+        }
+    }
+
+    /// Read each battery from the configured backend with its source for
+    /// error messages.
+    fn read_batts_raw(&self) -> Vec<(String, Result<BatteryReadout, StatusError>)> {
+        #[cfg(feature = "upower")]
+        if self.batt_backend == BattBackend::Upower {
+            match get_upower_batts() {
+                Ok(batts) => {
+                    return batts
+                        .into_iter()
+                        .map(|(model, info)| (model.unwrap_or_else(|| "UPower".into()), Ok(info)))
+                        .collect();
+                }
+                Err(err) => {
+                    self.failed("battery", &"UPower", err);
+                }
+            }
+        }
+
+        self.batts
+            .iter()
+            .map(|batt| (batt.display().to_string(), get_batt_raw(batt)))
+            .collect()
+    }
+
+    /// Read the combined readout of all the batteries from the configured
+    /// backend.
+    fn read_total_batt_raw(&self) -> Result<BatteryReadout, StatusError> {
+        #[cfg(feature = "upower")]
+        if self.batt_backend == BattBackend::Upower {
+            match get_upower_total() {
+                Ok(info) => return Ok(info),
+                Err(err) => {
+                    self.failed("battery", &"UPower", err);
+                }
+            }
+        }
+
+        get_total_batt_raw(&self.batts)
+    }
+
+    /// Return the next charging animation frame, if animation is enabled.
+    fn next_charge_frame(&self) -> Option<&str> {
+        if self.charge_frames.is_empty() {
+            return None;
+        }
+
+        let index = self.charge_frame.fetch_add(1, atomic::Ordering::Relaxed);
+        Some(&self.charge_frames[index % self.charge_frames.len()])
+    }
+
+    /// Return the ESSID and link quality of the configured wireless
+    /// interface or `None` if no interface is configured.
+    pub fn get_wifi(&self) -> Option<String> {
+        self.wifi_iface
+            .as_ref()
+            .map(|iface| get_wifi(iface).unwrap_or_else(|err| self.failed("wifi", iface, err)))
+    }
+
+    /// Return the volume of the configured mixer control or `None` if no
+    /// control is configured.
+    pub fn get_volume(&self) -> Option<String> {
+        self.mixer
+            .as_ref()
+            .map(|control| {
+                get_volume(control).unwrap_or_else(|err| self.failed("volume", control, err))
+            })
+    }
+
+    /// Return what the active media player is playing or `None` if nothing
+    /// is playing, it cannot be read, or the media section is disabled.
+    pub fn get_media(&self) -> Option<String> {
+        let width = self.media_width?;
+        get_media(width).unwrap_or_else(|err| {
+            self.failed("media", &"playerctl", err);
+            None
+        })
+    }
+
+    /// Return the number of unread mails in all the Maildirs, e.g. `3`, or
+    /// `None` if there are none.
+    pub fn get_mail(&self) -> Option<String> {
+        if self.maildirs.is_empty() {
+            return None;
+        }
+
+        let mut count = 0;
+        for maildir in &self.maildirs {
+            match get_mail_count(maildir) {
+                Ok(unread) => count += unread,
+                Err(err) => {
+                    self.failed("mail", &maildir.display(), err);
+                }
+            }
+        }
+        Some(count.to_string())
+    }
+
+    /// Return the weather fetched most recently, e.g. `8°C light rain`, or
+    /// `None` if the weather is not shown or has not been fetched yet.  The
+    /// weather is fetched in the background, see `fetch_in_background`.
+    #[cfg(feature = "weather")]
+    pub fn get_weather(&self) -> Option<String> {
+        let station = self.weather_station.clone()?;
+        let (readout, err) = fetch_in_background(&self.weather, self.weather_interval, {
+            let station = station.clone();
+            move || get_weather_raw(&station)
+        });
+        if let Some(err) = err {
+            self.failed("weather", &station, err);
+        }
+        readout.map(|readout| readout.format(self.temp_unit))
+    }
+
+    /// Return the weather, which is never shown without the `weather`
+    /// feature.
+    #[cfg(not(feature = "weather"))]
+    pub fn get_weather(&self) -> Option<String> {
+        None
+    }
+
+    /// Return the throughput of all the configured network interfaces since
+    /// the previous call, each labelled with its interface, e.g.
+    /// `eth0:↓1.2M ↑87.0K`, or `None` if there are none.  There is nothing to
+    /// compare against on the first call which returns `eth0:↓-- ↑--`.  See
+    /// `RwmStatus::set_net_ifaces` for `default` and wildcards.
+    pub fn get_network(&self) -> Option<String> {
+        if self.net_ifaces.is_empty() {
+            return None;
+        }
+
+        let now = Instant::now();
+        let net_strs: Vec<String> = self.net_ifaces
+            .iter()
+            .map(|iface| {
+                let (label, ifaces) = match resolve_net_ifaces(iface) {
+                    Ok(resolved) => resolved,
+                    Err(err) => return self.failed("network", iface, err),
+                };
+
+                let mut curr = (0, 0);
+                for name in &ifaces {
+                    match get_net_bytes(name) {
+                        Ok((rx, tx)) => curr = (curr.0 + rx, curr.1 + tx),
+                        Err(err) => return self.failed("network", name, err),
+                    }
+                }
+
+                // The counters of different interfaces cannot be compared.
+                let prev = self.prev_net
+                    .lock()
+                    .unwrap()
+                    .insert(iface.clone(), (ifaces.clone(), curr, now))
+                    .filter(|(prev_ifaces, _, _)| *prev_ifaces == ifaces);
+                let rates = prev
+                    .and_then(|(_, prev, then)| {
+                        net_rates(prev, curr, now.duration_since(then).as_secs_f64())
+                    })
+                    .unwrap_or_else(|| String::from("↓-- ↑--"));
+                format!("{}:{}", label, rates)
+            })
+            .collect();
+        Some(net_strs.join(&self.format.device_separator))
+    }
+
+    /// Return the VPN interfaces which are up, e.g. `wg0`, or `down` if none
+    /// are, followed by the public IP address if it is shown, e.g. `wg0
+    /// 203.0.113.7`.  Returns `None` if there are neither VPN interfaces nor a
+    /// public IP address to show.
+    pub fn get_vpn(&self) -> Option<String> {
+        let mut vpn_strs = vec![];
+        if !self.vpn_ifaces.is_empty() {
+            let ifaces = self.read_vpn_ifaces();
+            vpn_strs.push(if ifaces.is_empty() {
+                String::from("down")
+            } else {
+                ifaces.join(&self.format.device_separator)
+            });
+        }
+        if let Some(ip) = self.read_public_ip() {
+            vpn_strs.push(ip.to_string());
+        }
+
+        if vpn_strs.is_empty() {
+            return None;
+        }
+        Some(vpn_strs.join(" "))
+    }
+
+    /// Return the VPN interfaces which are up.  The public IP address is due
+    /// to be fetched again whenever this changes between none and some.
+    fn read_vpn_ifaces(&self) -> Vec<String> {
+        let mut up = vec![];
+        for iface in &self.vpn_ifaces {
+            match get_vpn_ifaces(iface) {
+                Ok(ifaces) => up.extend(ifaces),
+                Err(err) => {
+                    self.failed("vpn", iface, err);
+                }
+            }
+        }
+
+        let is_up = !up.is_empty();
+        let was_up = self.vpn_up.lock().unwrap().replace(is_up);
+        if was_up.is_some_and(|was_up| was_up != is_up) {
+            self.public_ip.lock().unwrap().fetched = None;
+        }
+        up
+    }
+
+    /// Return the public IP address fetched most recently, if it is shown,
+    /// see `fetch_in_background`.
+    fn read_public_ip(&self) -> Option<IpAddr> {
+        let url = self.public_ip_url.clone()?;
+        let (ip, err) = fetch_in_background(&self.public_ip, self.public_ip_interval, {
+            let url = url.clone();
+            move || get_public_ip(&url)
+        });
+        if let Some(err) = err {
+            self.failed("public IP", &url, err);
+        }
+        ip
+    }
+
+    /// Return the round trip time measured most recently, e.g. `23ms`,
+    /// `timeout` if the last probe failed, or `None` if the round trip time
+    /// is not measured or no probe has finished yet.  The probes run in the
+    /// background, see `fetch_in_background`.
+    pub fn get_ping(&self) -> Option<String> {
+        match self.read_ping()? {
+            Some(rtt) => Some(format!("{}ms", rtt.as_millis())),
+            None => Some(String::from("timeout")),
+        }
+    }
+
+    /// Return the round trip time measured by the last probe, `Some(None)`
+    /// if it failed.
+    fn read_ping(&self) -> Option<Option<Duration>> {
+        let host = self.ping_host.clone()?;
+        let timeout = self.ping_timeout;
+        let (rtt, err) = fetch_in_background(&self.ping, self.ping_interval, {
+            let host = host.clone();
+            move || get_ping(&host, timeout)
+        });
+        if let Some(err) = err {
+            self.failed("ping", &host, err);
+        }
+
+        if matches!(self.ping.lock().unwrap().fetched, Some((_, false))) {
+            return Some(None);
+        }
+        rtt.map(Some)
+    }
+
+    /// Return the time since boot, e.g. `up 3d 4:12`.
+    #[inline]
+    pub fn get_uptime(&self) -> String {
+        get_uptime().unwrap_or_else(|err| self.failed("uptime", &"/proc/uptime", err))
+    }
+
+    /// Return the brightness of the first backlight.
+    pub fn get_brightness(&self) -> Option<String> {
+        self.backlights
+            .first()
+            .map(|backlight| {
+                get_brightness(backlight)
+                    .unwrap_or_else(|err| self.failed("brightness", &backlight.display(), err))
+            })
+    }
+
+    /// Return times for all configured time zones.
+    pub fn get_times(&self) -> String {
+        let mut tz_strs: Vec<String> = self.tzs
+            .iter()
+            .map(|tz| {
+                format!(
+                    "{}:{}",
+                    tz.label,
+                    get_tz_time(&tz.name, &tz.format)
+                        .unwrap_or_else(|err| self.failed("time", &tz.name, err))
+                )
+            })
+            .collect();
+        tz_strs.push(get_local_time(&self.time_format));
+        tz_strs.join(" ")
+    }
+
+    /// Return the readout of the monitor with the provided id or `None` if
+    /// there is no such monitor.
+    pub fn get_monitor(&self, id: &str) -> Option<String> {
+        self.monitors
+            .iter()
+            .find(|monitor| monitor.id() == id)
+            .map(|monitor| self.read_monitor(monitor.as_ref()))
+    }
+
+    /// Read the provided monitor, a failed readout is shown as the
+    /// placeholder.
+    fn read_monitor(&self, monitor: &dyn Monitor) -> String {
+        monitor.read().unwrap_or_else(|err| self.failed(monitor.id(), &"monitor", err))
+    }
+
+    /// Log the error of a failed readout from the provided source and return
+    /// the placeholder shown in its place.  A readout which keeps failing the
+    /// same way is only logged as a warning the first time so that the log
+    /// is not flooded on every update.
+    fn failed(&self, readout: &str, source: &dyn Display, err: StatusError) -> String {
+        let message = err.to_string();
+        let mut failures = self.failures.lock().unwrap();
+        let key = format!("{} {}", readout, source);
+        if failures.get(&key) == Some(&message) {
+            debug!("{} read failed for {}: {}", readout, source, message);
+        } else {
+            warn!("{} read failed for {}: {}", readout, source, message);
+            failures.insert(key, message);
+        }
+        self.placeholder.clone()
+    }
+
+    /// Read every discovered device once and return the readout or error for
+    /// each of them.  This is useful to check if the hardware is supported.
+    pub fn self_test(&self) -> Vec<(String, Result<String, StatusError>)> {
+        let mut results = vec![];
+
+        for hw_mon in &self.hw_mons {
+            results.push((hw_mon.display().to_string(), get_temp(hw_mon, self.temp_unit)));
+        }
+
+        #[cfg(feature = "ipmi")]
+        {
+            for hw_mon in &self.ipmi_mons {
+                let temps = get_ipmi_temps(std::slice::from_ref(hw_mon), self.temp_unit);
+                results.push((hw_mon.display().to_string(), temps));
+            }
+            if self.ipmitool {
+                results.push((String::from("ipmitool"), get_ipmi_temps(&[], self.temp_unit)));
+            }
+        }
+
+        for batt in &self.batts {
+            results.push((batt.display().to_string(), self.read_batt(batt, None)));
+        }
+
+        results
+    }
+
+    /// Return the full status line with all the readouts.
+    pub fn get_status(&self) -> String {
+        self.snapshot().format(&self.format)
+    }
+
+    /// Return the readouts of the provided section or `None` if there are no
+    /// devices for it.
+    pub fn get_section(&self, section: &Section) -> Option<String> {
+        match section {
+            Section::Temperatures => self.get_temperatures(),
+            Section::Fans => self.get_fans(),
+            Section::Gpu => self.get_gpus(),
+            Section::LoadAvgs => Some(self.get_load_avgs()),
+            Section::Cpu => Some(self.get_cpu_usage()),
+            Section::Freq => self.get_cpu_freq(),
+            Section::Memory => Some(self.get_memory()),
+            Section::Swap => self.get_swap(),
+            Section::Disks => self.get_disks(),
+            Section::Batteries => self.get_batteries(),
+            Section::Wifi => self.get_wifi(),
+            Section::Network => self.get_network(),
+            Section::Vpn => self.get_vpn(),
+            Section::Ping => self.get_ping(),
+            Section::Brightness => self.get_brightness(),
+            Section::Volume => self.get_volume(),
+            Section::Media => self.get_media(),
+            Section::Mail => self.get_mail(),
+            Section::Weather => self.get_weather(),
+            Section::Uptime => Some(self.get_uptime()),
+            Section::Times => Some(self.get_times()),
+            Section::Monitor(id) => self.get_monitor(id),
+        }
+    }
+
+    /// Return the readouts of the provided section like
+    /// `RwmStatus::get_section`, but reuse the previous ones until its
+    /// interval has passed, see `RwmStatus::set_interval`.
+    pub fn read_section(&self, section: &Section) -> Option<String> {
+        let interval = match self.interval(section) {
+            Some(interval) => interval,
+            None => return self.get_section(section),
+        };
+
+        if let Some((read_at, readout)) = self.readouts.lock().unwrap().get(section.name()) {
+            if read_at.elapsed() < interval {
+                return readout.clone();
+            }
+        }
+
+        let read_at = Instant::now();
+        let readout = self.get_section(section);
+        self.readouts
+            .lock()
+            .unwrap()
+            .insert(String::from(section.name()), (read_at, readout.clone()));
+        readout
+    }
+
+    /// Read the provided section on the next update even if its interval has
+    /// not passed yet, e.g. after an event such as a plugged in charger.
+    pub fn invalidate(&self, section: &Section) {
+        self.readouts.lock().unwrap().remove(section.name());
+    }
+
+    /// Read all the sections on the next update even if their intervals
+    /// have not passed yet.
+    pub fn invalidate_all(&self) {
+        self.readouts.lock().unwrap().clear();
+    }
+
+    /// Return the interval of the provided section, if it has one.
+    fn interval(&self, section: &Section) -> Option<Duration> {
+        let interval = self.intervals
+            .iter()
+            .find(|(known, _)| known == section)
+            .map(|(_, interval)| *interval);
+
+        match section {
+            Section::Monitor(id) => interval.or_else(|| {
+                self.monitors
+                    .iter()
+                    .find(|monitor| monitor.id() == id)
+                    .and_then(|monitor| monitor.interval())
+            }),
+            _ => interval,
+        }
+    }
+
+    /// Return the time until the readouts of the first section with an
+    /// interval are due or `None` if no section has an interval.  Sections
+    /// which have not been read yet are due immediately.
+    pub fn next_update(&self) -> Option<Duration> {
+        let readouts = self.readouts.lock().unwrap();
+        Section::ALL
+            .iter()
+            .cloned()
+            .chain(self.monitors.iter().map(|monitor| {
+                Section::Monitor(String::from(monitor.id()))
+            }))
+            .filter_map(|section| {
+                let interval = self.interval(&section)?;
+                Some(match readouts.get(section.name()) {
+                    Some((read_at, _)) => interval.saturating_sub(read_at.elapsed()),
+                    None => Duration::from_secs(0),
+                })
+            })
+            .min()
+    }
+
+    /// Return the section with the provided name, either a built-in section
+    /// or one of the added monitors.
+    fn find_section(&self, name: &str) -> Option<Section> {
+        Section::from_name(name).or_else(|| {
+            self.monitors
+                .iter()
+                .find(|monitor| monitor.id() == name)
+                .map(|monitor| Section::Monitor(String::from(monitor.id())))
+        })
+    }
+
+    /// Render the provided template.  Placeholders with a section name, e.g.
+    /// `{temp}` or `{batt}` (see `Section::name`), are replaced by the
+    /// corresponding readouts, placeholders with a sub-field, e.g.
+    /// `{batt:percent}`, by a single value (see `RwmStatus::get_subfield`),
+    /// and unknown placeholders are left as they are.
+    pub fn render(&self, template: &str) -> String {
+        render_template(template, |field| self.render_field(field))
+    }
+
+    /// Return the readout for the provided template field or `None` if there
+    /// is no such field.  A field is either a section name, e.g. `batt`, or a
+    /// section name and one of its sub-fields, e.g. `batt:percent`, see
+    /// `RwmStatus::get_subfield`.  Missing devices render as an empty string.
+    pub fn render_field(&self, field: &str) -> Option<String> {
+        match field.split_once(':') {
+            Some((name, subfield)) => self
+                .find_section(name)
+                .and_then(|section| self.get_subfield(&section, subfield)),
+            None => self
+                .find_section(field)
+                .map(|section| self.get_section(&section).unwrap_or_default()),
+        }
+    }
+
+    /// Return a single value of the provided section for a template
+    /// placeholder such as `{batt:percent}` or `None` if the section has no
+    /// such sub-field.  The sub-fields are:
+    ///
+    /// * `temp:max` - the highest temperature, e.g. `52°C`,
+    /// * `gpu:temp`, `gpu:busy`, `gpu:clock`, `gpu:power` - the temperature,
+    ///   utilization, clock, and power of each GPU, e.g. `67°C`, `45`,
+    ///   `1800MHz`, and `42.5W`,
+    /// * `load:1`, `load:5`, `load:15` - a single load average,
+    /// * `batt:percent`, `batt:state`, `batt:time`, `batt:power`,
+    ///   `batt:health`, `batt:cycles` - the charge percentage, status symbol,
+    ///   time remaining, power, health, and charge cycles of each battery,
+    ///   e.g. `63`, `-`, `1:47`, `-11.3W`, `87`, and `412`,
+    /// * `time:local` - the local time and `time:A` the time in the time
+    ///   zone labelled `A`.
+    pub fn get_subfield(&self, section: &Section, subfield: &str) -> Option<String> {
+        if !self.has_subfield(section, subfield) {
+            return None;
+        }
+
+        Some(match section {
+            Section::Temperatures => self.get_max_temperature(),
+            Section::Gpu => {
+                let gpu_strs: Vec<String> = self
+                    .read_gpus_raw()
+                    .into_iter()
+                    .map(|(source, gpu)| match gpu {
+                        Ok(gpu) => match subfield {
+                            "temp" => gpu
+                                .millidegrees
+                                .map(|millidegrees| {
+                                    let degrees = self.temp_unit.degrees(millidegrees);
+                                    format_temp(None, degrees, self.temp_unit)
+                                })
+                                .unwrap_or_default(),
+                            "busy" => {
+                                gpu.busy.map(|busy| format!("{:.0}", busy)).unwrap_or_default()
+                            }
+                            "clock" => {
+                                gpu.clock.map(|clock| format!("{}MHz", clock)).unwrap_or_default()
+                            }
+                            _ => {
+                                gpu.power.map(|power| format!("{:.1}W", power)).unwrap_or_default()
+                            }
+                        },
+                        Err(err) => self.failed("gpu", &source, err),
+                    })
+                    .collect();
+                gpu_strs.join(&self.format.device_separator)
+            }
+            Section::LoadAvgs => {
+                let idx = match subfield {
+                    "1" => 0,
+                    "5" => 1,
+                    _ => 2,
+                };
+                match get_load_avgs() {
+                    Ok(avgs) => {
+                        avgs.split_whitespace().nth(idx).map(String::from).unwrap_or_default()
+                    }
+                    Err(err) => self.failed("load", &"/proc/loadavg", err),
+                }
+            }
+            Section::Batteries => {
+                let batt_strs: Vec<String> = self
+                    .read_batts_raw()
+                    .into_iter()
+                    .map(|(source, info)| match info {
+                        Ok(info) => match subfield {
+                            "percent" => format!("{:.0}", info.percent),
+                            "state" => info.state.to_string(),
+                            "power" => info
+                                .power
+                                .map(|power| format_batt_power(power, info.state))
+                                .unwrap_or_default(),
+                            "health" => info
+                                .health
+                                .map(|health| format!("{:.0}", health))
+                                .unwrap_or_default(),
+                            "cycles" => info
+                                .cycles
+                                .map(|cycles| cycles.to_string())
+                                .unwrap_or_default(),
+                            _ => info.time_remaining_str().unwrap_or_default(),
+                        },
+                        Err(err) => self.failed("battery", &source, err),
+                    })
+                    .collect();
+                batt_strs.join(&self.format.device_separator)
+            }
+            Section::Times if subfield == "local" => get_local_time(&self.time_format),
+            Section::Times => {
+                let tz = self.tzs.iter().find(|tz| tz.label.to_string() == subfield).unwrap();
+                get_tz_time(&tz.name, &tz.format)
+                    .unwrap_or_else(|err| self.failed("time", &tz.name, err))
+            }
+            _ => unreachable!(),
+        })
+    }
+
+    /// Return true if the provided section has the sub-field, see
+    /// `RwmStatus::get_subfield`.
+    fn has_subfield(&self, section: &Section, subfield: &str) -> bool {
+        match section {
+            Section::Temperatures => subfield == "max",
+            Section::Gpu => ["temp", "busy", "clock", "power"].contains(&subfield),
+            Section::LoadAvgs => ["1", "5", "15"].contains(&subfield),
+            Section::Batteries => {
+                ["percent", "state", "time", "power", "health", "cycles"].contains(&subfield)
+            }
+            Section::Times => {
+                subfield == "local" || self.tzs.iter().any(|tz| tz.label.to_string() == subfield)
+            }
+            _ => false,
+        }
+    }
+
+    /// Return the highest temperature of all the sensors, e.g. `52°C`.
+    fn get_max_temperature(&self) -> String {
+        self.max_millidegrees()
+            .map(|max| format_temp(None, self.temp_unit.degrees(max), self.temp_unit))
+            .unwrap_or_default()
+    }
+
+    /// Return the highest temperature of all the sensors in millidegrees
+    /// Celsius or `None` if there are none.
+    fn max_millidegrees(&self) -> Option<f64> {
+        let mut max: Option<f64> = None;
+        for hw_mon in &self.hw_mons {
+            match self.read_temps(hw_mon) {
+                Ok(temps) => {
+                    for temp in temps {
+                        let degrees = temp.millidegrees;
+                        max = Some(max.map_or(degrees, |max| max.max(degrees)));
+                    }
+                }
+                Err(err) => {
+                    self.failed("temp", &hw_mon.display(), err);
+                }
+            }
+        }
+
+        max
+    }
+
+    /// Return the value of the provided section which thresholds are
+    /// compared against or `None` if the section has none or there are no
+    /// devices for it:
+    ///
+    /// * `Section::Temperatures` - the highest temperature in the configured
+    ///   unit,
+    /// * `Section::Fans` - the highest fan speed in RPM,
+    /// * `Section::Gpu` - the highest GPU temperature in the configured unit,
+    /// * `Section::Batteries` - the lowest charge percentage or the combined
+    ///   one depending on the battery mode,
+    /// * `Section::LoadAvgs` - the 1 minute load average,
+    /// * `Section::Cpu` - the CPU usage measured by its last readout,
+    /// * `Section::Freq` - the current CPU frequency in GHz,
+    /// * `Section::Memory` - the used memory as a percentage of the total,
+    /// * `Section::Disks` - the lowest free space percentage of the mount
+    ///   points,
+    /// * `Section::Vpn` - 1 if a VPN interface is up and 0 otherwise,
+    /// * `Section::Ping` - the round trip time in milliseconds, infinite after
+    ///   a failed probe,
+    /// * `Section::Mail` - the number of unread mails,
+    /// * `Section::Weather` - the temperature in the configured unit.
+    pub fn get_value(&self, section: &Section) -> Option<f64> {
+        match section {
+            Section::Temperatures => {
+                self.max_millidegrees().map(|max| self.temp_unit.degrees(max) as f64)
+            }
+            Section::Fans => self.hw_mons
+                .iter()
+                .filter_map(|hw_mon| get_fans_raw(hw_mon).ok())
+                .flatten()
+                .map(|fan| fan.rpm as f64)
+                .fold(None, |max: Option<f64>, rpm| Some(max.map_or(rpm, |max| max.max(rpm)))),
+            Section::Gpu => self
+                .read_gpus_raw()
+                .into_iter()
+                .filter_map(|(_, gpu)| gpu.ok()?.millidegrees)
+                .map(|millidegrees| self.temp_unit.degrees(millidegrees) as f64)
+                .fold(None, |max: Option<f64>, degrees| {
+                    Some(max.map_or(degrees, |max| max.max(degrees)))
+                }),
+            Section::Batteries if self.batt_mode == BattMode::Combined => {
+                self.read_total_batt_raw().ok().map(|info| info.percent)
+            }
+            Section::Batteries => self
+                .read_batts_raw()
+                .into_iter()
+                .filter_map(|(_, info)| info.ok())
+                .map(|info| info.percent)
+                .fold(None, |min: Option<f64>, percent| {
+                    Some(min.map_or(percent, |min| min.min(percent)))
+                }),
+            Section::LoadAvgs => get_load_avgs_n(1).ok()?.parse().ok(),
+            Section::Cpu => *self.cpu_usage.lock().unwrap(),
+            Section::Freq => {
+                let freqs = get_cpu_freqs(&self.cpus).ok()?;
+                Some(combine_freqs(&freqs, self.freq_mode) / 1_000_000.0)
+            }
+            Section::Memory => get_mem_raw().ok().map(|mem| mem.percent()),
+            Section::Disks => self.mounts
+                .iter()
+                .filter_map(|mount| get_disk_raw(mount).ok())
+                .map(|disk| disk.percent_free())
+                .fold(None, |min: Option<f64>, percent| {
+                    Some(min.map_or(percent, |min| min.min(percent)))
+                }),
+            Section::Vpn if self.vpn_ifaces.is_empty() => None,
+            Section::Vpn => Some(if self.read_vpn_ifaces().is_empty() { 0.0 } else { 1.0 }),
+            Section::Ping => match self.read_ping()? {
+                Some(rtt) => Some(rtt.as_secs_f64() * 1000.0),
+                None => Some(f64::INFINITY),
+            },
+            Section::Mail => self.get_mail()?.parse().ok(),
+            #[cfg(feature = "weather")]
+            Section::Weather => {
+                let cache = self.weather.lock().unwrap();
+                let celsius = cache.readout.as_ref()?.celsius;
+                Some(self.temp_unit.degrees(celsius * 1000.0) as f64)
+            }
+            _ => None,
+        }
+    }
+
+    /// Return the messages of the alerts raised since the last call, see
+    /// `RwmStatus::add_alert`.  The values are those of the provided snapshot
+    /// and placeholders such as `{batt}` in the messages are replaced by its
+    /// readouts.
+    pub fn take_alerts(&self, snapshot: &StatusSnapshot) -> Vec<String> {
+        let mut messages = vec![];
+        for alert in &self.alerts {
+            let value = match alert.mount {
+                Some(ref mount) => snapshot.values.disk(mount),
+                None => snapshot.values.get(&alert.section),
+            };
+            let value = match value {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let discharging =
+                alert.section != Section::Batteries || snapshot.values.discharging;
+            if discharging && alert.limit.crossed(value) {
+                if !alert.raised.swap(true, atomic::Ordering::SeqCst) {
+                    messages.push(snapshot.render(&alert.message));
+                }
+            } else if !discharging || alert.limit.recovered(value, alert.margin) {
+                alert.raised.store(false, atomic::Ordering::SeqCst);
+            }
+        }
+        messages
+    }
+
+    /// Take over which alerts are raised from the provided status, e.g. the
+    /// one a reloaded configuration replaces, so that they are not raised
+    /// again.  Alerts are matched by their section, mount point, limit, and
+    /// message.
+    pub fn inherit_alerts(&self, previous: &RwmStatus) {
+        for alert in &self.alerts {
+            let raised = previous.alerts.iter().any(|old| {
+                old.section == alert.section
+                    && old.mount == alert.mount
+                    && old.limit == alert.limit
+                    && old.message == alert.message
+                    && old.raised.load(atomic::Ordering::SeqCst)
+            });
+            alert.raised.store(raised, atomic::Ordering::SeqCst);
+        }
+    }
+
+    /// Return true if any battery is discharging.
+    fn batt_discharging(&self) -> bool {
+        if self.batt_mode == BattMode::Combined {
+            return self
+                .read_total_batt_raw()
+                .is_ok_and(|info| info.state == ChargeState::Discharging);
+        }
+
+        self.read_batts_raw()
+            .into_iter()
+            .any(|(_, info)| info.is_ok_and(|info| info.state == ChargeState::Discharging))
+    }
 
-// External imports
-use chrono::prelude::*;
+    /// Read the values of the sections and the mount points which have a
+    /// threshold or an alert, each only once.
+    fn get_values(&self) -> SectionValues {
+        let mut values = SectionValues::default();
+        let mut read: Vec<&Section> = vec![];
+        let alert_sections = self.alerts
+            .iter()
+            .filter(|alert| alert.mount.is_none())
+            .map(|alert| &alert.section);
+        let sections = self.thresholds.iter().map(|(section, _, _)| section).chain(alert_sections);
+        for section in sections {
+            if !read.contains(&section) {
+                read.push(section);
+                if let Some(value) = self.get_value(section) {
+                    values.sections.push((section.clone(), value));
+                }
+            }
+        }
+
+        let mut read: Vec<&PathBuf> = vec![];
+        let mounts = self.disk_thresholds
+            .iter()
+            .map(|(mount, _, _)| mount)
+            .chain(self.alerts.iter().filter_map(|alert| alert.mount.as_ref()));
+        for mount in mounts {
+            if !read.contains(&mount) {
+                read.push(mount);
+                if let Ok(disk) = get_disk_raw(mount) {
+                    values.disks.push((mount.clone(), disk.percent_free()));
+                }
+            }
+        }
+
+        if self.alerts.iter().any(|alert| alert.section == Section::Batteries) {
+            values.discharging = self.batt_discharging();
+        }
+        values
+    }
+
+    /// Return the colours of the sections whose value crossed a threshold.
+    fn get_colors(&self, values: &SectionValues) -> Vec<(Section, String)> {
+        let mut colors: Vec<(Section, String)> = vec![];
+        for (section, limit, color) in &self.thresholds {
+            if values.get(section).is_some_and(|value| limit.crossed(value)) {
+                colors.retain(|(colored, _)| colored != section);
+                colors.push((section.clone(), color.clone()));
+            }
+        }
+
+        for (mount, limit, color) in &self.disk_thresholds {
+            if values.disk(mount).is_some_and(|value| limit.crossed(value)) {
+                colors.retain(|(colored, _)| *colored != Section::Disks);
+                colors.push((Section::Disks, color.clone()));
+            }
+        }
+        colors
+    }
+
+    /// Return the sub-field readouts used by the template of the layout.
+    fn get_template_fields(&self) -> Vec<(String, String)> {
+        let mut fields: Vec<(String, String)> = vec![];
+        if let Some(ref template) = self.format.template {
+            render_template(template, |field| {
+                if field.contains(':') && !fields.iter().any(|(name, _)| name == field) {
+                    if let Some(value) = self.render_field(field) {
+                        fields.push((String::from(field), value));
+                    }
+                }
+                None
+            });
+        }
+        fields
+    }
+
+    /// Check that the template only uses known placeholders.
+    pub fn check_template(&self, template: &str) -> Result<(), StatusError> {
+        let mut unknown = vec![];
+        render_template(template, |field| {
+            let known = match field.split_once(':') {
+                Some((name, subfield)) => self
+                    .find_section(name)
+                    .is_some_and(|section| self.has_subfield(&section, subfield)),
+                None => self.find_section(field).is_some(),
+            };
+            if !known {
+                unknown.push(format!("{{{}}}", field));
+            }
+            None
+        });
+
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(StatusError::InvalidArg(format!("unknown placeholder {}", unknown.join(", "))))
+        }
+    }
+
+    /// Collect all the readouts in parallel, one thread per section.  The
+    /// result is the same as that of `snapshot`, but a slow device only holds
+    /// up its own section.
+    pub fn collect_all(&self) -> StatusSnapshot {
+        thread::scope(|scope| {
+            let temperatures = scope.spawn(|| self.read_section(&Section::Temperatures));
+            let fans = scope.spawn(|| self.read_section(&Section::Fans));
+            let gpus = scope.spawn(|| self.read_section(&Section::Gpu));
+            let load_avgs = scope.spawn(|| self.read_section(&Section::LoadAvgs));
+            let cpu = scope.spawn(|| self.read_section(&Section::Cpu));
+            let freq = scope.spawn(|| self.read_section(&Section::Freq));
+            let memory = scope.spawn(|| self.read_section(&Section::Memory));
+            let swap = scope.spawn(|| self.read_section(&Section::Swap));
+            let disks = scope.spawn(|| self.read_section(&Section::Disks));
+            let batteries = scope.spawn(|| self.read_section(&Section::Batteries));
+            let wifi = scope.spawn(|| self.read_section(&Section::Wifi));
+            let network = scope.spawn(|| self.read_section(&Section::Network));
+            let vpn = scope.spawn(|| self.read_section(&Section::Vpn));
+            let ping = scope.spawn(|| self.read_section(&Section::Ping));
+            let brightness = scope.spawn(|| self.read_section(&Section::Brightness));
+            let volume = scope.spawn(|| self.read_section(&Section::Volume));
+            let media = scope.spawn(|| self.read_section(&Section::Media));
+            let mail = scope.spawn(|| self.read_section(&Section::Mail));
+            let weather = scope.spawn(|| self.read_section(&Section::Weather));
+            let uptime = scope.spawn(|| self.read_section(&Section::Uptime));
+            let times = scope.spawn(|| self.read_section(&Section::Times));
+            let fields = scope.spawn(|| self.get_template_fields());
+            let values = scope.spawn(|| self.get_values());
+            let monitors: Vec<_> = self.monitors
+                .iter()
+                .map(|monitor| {
+                    let section = Section::Monitor(String::from(monitor.id()));
+                    scope.spawn(move || self.read_section(&section).unwrap_or_default())
+                })
+                .collect();
 
-/// Return temperature read from the provided monitor.
-pub fn get_temp(hwmon: &PathBuf) -> Result<String, StatusError> {
-    let val: i64 = read_to_string(hwmon.join("temp1_input"))?.trim().parse()?;
-    Ok(format!("{:02}°C", val / 1000))
+            let values = values.join().unwrap();
+            StatusSnapshot {
+                temperatures: temperatures.join().unwrap(),
+                fans: fans.join().unwrap(),
+                gpus: gpus.join().unwrap(),
+                load_avgs: load_avgs.join().unwrap().unwrap_or_default(),
+                cpu: cpu.join().unwrap().unwrap_or_default(),
+                freq: freq.join().unwrap(),
+                memory: memory.join().unwrap().unwrap_or_default(),
+                swap: swap.join().unwrap(),
+                disks: disks.join().unwrap(),
+                batteries: batteries.join().unwrap(),
+                wifi: wifi.join().unwrap(),
+                network: network.join().unwrap(),
+                vpn: vpn.join().unwrap(),
+                ping: ping.join().unwrap(),
+                brightness: brightness.join().unwrap(),
+                volume: volume.join().unwrap(),
+                media: media.join().unwrap(),
+                mail: mail.join().unwrap(),
+                weather: weather.join().unwrap(),
+                uptime: uptime.join().unwrap().unwrap_or_default(),
+                times: times.join().unwrap().unwrap_or_default(),
+                monitors: self.monitors
+                    .iter()
+                    .zip(monitors)
+                    .map(|(monitor, readout)| {
+                        (String::from(monitor.id()), readout.join().unwrap())
+                    })
+                    .collect(),
+                fields: fields.join().unwrap(),
+                colors: self.get_colors(&values),
+                values,
+            }
+        })
+    }
+
+    /// Collect all the readouts.  Sections with an interval reuse their
+    /// previous readouts until it has passed, see `RwmStatus::set_interval`.
+    pub fn snapshot(&self) -> StatusSnapshot {
+        let values = self.get_values();
+        StatusSnapshot {
+            temperatures: self.read_section(&Section::Temperatures),
+            fans: self.read_section(&Section::Fans),
+            gpus: self.read_section(&Section::Gpu),
+            load_avgs: self.read_section(&Section::LoadAvgs).unwrap_or_default(),
+            cpu: self.read_section(&Section::Cpu).unwrap_or_default(),
+            freq: self.read_section(&Section::Freq),
+            memory: self.read_section(&Section::Memory).unwrap_or_default(),
+            swap: self.read_section(&Section::Swap),
+            disks: self.read_section(&Section::Disks),
+            batteries: self.read_section(&Section::Batteries),
+            wifi: self.read_section(&Section::Wifi),
+            network: self.read_section(&Section::Network),
+            vpn: self.read_section(&Section::Vpn),
+            ping: self.read_section(&Section::Ping),
+            brightness: self.read_section(&Section::Brightness),
+            volume: self.read_section(&Section::Volume),
+            media: self.read_section(&Section::Media),
+            mail: self.read_section(&Section::Mail),
+            weather: self.read_section(&Section::Weather),
+            uptime: self.read_section(&Section::Uptime).unwrap_or_default(),
+            times: self.read_section(&Section::Times).unwrap_or_default(),
+            monitors: self.monitors
+                .iter()
+                .map(|monitor| {
+                    let section = Section::Monitor(String::from(monitor.id()));
+                    (String::from(monitor.id()), self.read_section(&section).unwrap_or_default())
+                })
+                .collect(),
+            fields: self.get_template_fields(),
+            colors: self.get_colors(&values),
+            values,
+        }
+    }
 }
 
-/// Return the three load average values.
-pub fn get_load_avgs() -> Result<String, StatusError> {
-    let mut avgs: [libc::c_double; 3] = [0.0; 3];
+/// ## StatusSnapshot
+///
+/// The readouts collected at a single point in time.  Its `Display`
+/// implementation renders the status line with the default `StatusFormat`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatusSnapshot {
+    pub temperatures: Option<String>,
+    pub fans: Option<String>,
+    pub gpus: Option<String>,
+    pub load_avgs: String,
+    pub cpu: String,
+    pub freq: Option<String>,
+    pub memory: String,
+    pub swap: Option<String>,
+    pub disks: Option<String>,
+    pub batteries: Option<String>,
+    pub wifi: Option<String>,
+    pub network: Option<String>,
+    pub vpn: Option<String>,
+    pub ping: Option<String>,
+    pub brightness: Option<String>,
+    pub volume: Option<String>,
+    pub media: Option<String>,
+    pub mail: Option<String>,
+    pub weather: Option<String>,
+    pub uptime: String,
+    pub times: String,
+    /// The readouts of the added monitors by id.
+    pub monitors: Vec<(String, String)>,
+    /// The readouts of the sub-fields used by the template of the layout by
+    /// placeholder, e.g. `batt:percent`.
+    pub fields: Vec<(String, String)>,
+    /// The colours of the sections which crossed a threshold, see
+    /// `RwmStatus::add_threshold`.
+    pub colors: Vec<(Section, String)>,
+    /// The values the thresholds and the alerts are compared against.
+    pub values: SectionValues,
+}
 
-    let rc = unsafe { libc::getloadavg(&mut avgs[0] as *mut libc::c_double, 3) };
-    if rc < 0 {
-        return Err(StatusError::System(rc));
+impl StatusSnapshot {
+    /// Render the provided template, see `RwmStatus::render`.
+    pub fn render(&self, template: &str) -> String {
+        render_template(template, |field| self.render_field(field))
+    }
+
+    /// Return the readout for the provided template field name, see
+    /// `RwmStatus::render_field`.
+    pub fn render_field(&self, field: &str) -> Option<String> {
+        if field.contains(':') {
+            return self
+                .fields
+                .iter()
+                .find(|(name, _)| name == field)
+                .map(|(_, readout)| readout.clone());
+        }
+
+        match Section::from_name(field) {
+            Some(section) => Some(self.section(&section).unwrap_or_default()),
+            None => self.section(&Section::Monitor(String::from(field))),
+        }
+    }
+
+    /// Return the readouts of the provided section, see
+    /// `RwmStatus::get_section`.
+    pub fn section(&self, section: &Section) -> Option<String> {
+        match section {
+            Section::Temperatures => self.temperatures.clone(),
+            Section::Fans => self.fans.clone(),
+            Section::Gpu => self.gpus.clone(),
+            Section::LoadAvgs => Some(self.load_avgs.clone()),
+            Section::Cpu => Some(self.cpu.clone()),
+            Section::Freq => self.freq.clone(),
+            Section::Memory => Some(self.memory.clone()),
+            Section::Swap => self.swap.clone(),
+            Section::Disks => self.disks.clone(),
+            Section::Batteries => self.batteries.clone(),
+            Section::Wifi => self.wifi.clone(),
+            Section::Network => self.network.clone(),
+            Section::Vpn => self.vpn.clone(),
+            Section::Ping => self.ping.clone(),
+            Section::Brightness => self.brightness.clone(),
+            Section::Volume => self.volume.clone(),
+            Section::Media => self.media.clone(),
+            Section::Mail => self.mail.clone(),
+            Section::Weather => self.weather.clone(),
+            Section::Uptime => Some(self.uptime.clone()),
+            Section::Times => Some(self.times.clone()),
+            Section::Monitor(id) => self
+                .monitors
+                .iter()
+                .find(|(monitor, _)| monitor == id)
+                .map(|(_, readout)| readout.clone()),
+        }
+    }
+
+    /// Render the status line with the provided layout, from its template if
+    /// it has one.  Sections without any devices are left out.  Only the
+    /// sub-fields of the template of the layout the snapshot was taken with
+    /// are known.
+    pub fn format(&self, format: &StatusFormat) -> String {
+        if let Some(ref template) = format.template {
+            return render_template(template, |field| {
+                let readout = self.render_field(field)?;
+                let name = field.split(':').next().unwrap_or(field);
+                let section = Section::from_name(name)
+                    .unwrap_or_else(|| Section::Monitor(String::from(name)));
+                Some(format.markup.apply(&readout, self.color(&section)))
+            });
+        }
+
+        let stats: Vec<String> = self
+            .blocks(format)
+            .iter()
+            .map(|block| format.markup.apply(&block.full_text, block.color.as_deref()))
+            .collect();
+        stats.join(&format.separator)
+    }
+
+    /// Return the colour of the provided section if it crossed a threshold.
+    pub fn color(&self, section: &Section) -> Option<&str> {
+        self.colors
+            .iter()
+            .find(|(colored, _)| colored == section)
+            .map(|(_, color)| &color[..])
+    }
+
+    /// Return a block for each section of the provided layout, e.g. for bars
+    /// such as i3bar which draw each section separately.  Sections without
+    /// any devices are left out.
+    pub fn blocks(&self, format: &StatusFormat) -> Vec<Block> {
+        format
+            .sections
+            .iter()
+            .filter_map(|(section, prefix)| {
+                self.section(section).map(|readout| Block {
+                    name: String::from(section.name()),
+                    full_text: format!("{}{}", prefix, readout),
+                    color: self.color(section).map(String::from),
+                })
+            })
+            .collect()
     }
 
-    Ok(format!("{:.2} {:.2} {:.2}", avgs[0], avgs[1], avgs[2]))
+    /// Return the readouts as a JSON object.  Missing readouts are `null`.
+    pub fn to_json(&self) -> String {
+        let fields = [
+            ("temperatures", self.temperatures.as_ref()),
+            ("fans", self.fans.as_ref()),
+            ("gpus", self.gpus.as_ref()),
+            ("load_avgs", Some(&self.load_avgs)),
+            ("cpu", Some(&self.cpu)),
+            ("freq", self.freq.as_ref()),
+            ("memory", Some(&self.memory)),
+            ("swap", self.swap.as_ref()),
+            ("disks", self.disks.as_ref()),
+            ("batteries", self.batteries.as_ref()),
+            ("wifi", self.wifi.as_ref()),
+            ("network", self.network.as_ref()),
+            ("vpn", self.vpn.as_ref()),
+            ("ping", self.ping.as_ref()),
+            ("brightness", self.brightness.as_ref()),
+            ("volume", self.volume.as_ref()),
+            ("media", self.media.as_ref()),
+            ("mail", self.mail.as_ref()),
+            ("weather", self.weather.as_ref()),
+            ("uptime", Some(&self.uptime)),
+            ("times", Some(&self.times)),
+        ];
+
+        let mut field_strs: Vec<String> = fields
+            .iter()
+            .map(|(key, value)| match value {
+                Some(value) => format!("\"{}\":{}", key, json_string(value)),
+                None => format!("\"{}\":null", key),
+            })
+            .collect();
+
+        let monitor_strs: Vec<String> = self
+            .monitors
+            .iter()
+            .map(|(id, readout)| format!("{}:{}", json_string(id), json_string(readout)))
+            .collect();
+        field_strs.push(format!("\"monitors\":{{{}}}", monitor_strs.join(",")));
+        format!("{{{}}}", field_strs.join(","))
+    }
 }
 
-/// Return battery status for the battery at the provided path.
-pub fn get_batt(batt: &PathBuf) -> Result<String, StatusError> {
-    if !read_to_string(batt.join("present"))?.starts_with('1') {
-        return Err(StatusError::NotPresent(batt.to_str().unwrap().to_string()));
+impl std::fmt::Display for StatusSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.format(&StatusFormat::default()))
     }
+}
 
-    let design_capacity: u64 = read_to_string(batt.join("charge_full_design"))
-        .or_else(|_| read_to_string(batt.join("energy_full_design")))?
-        .trim()
-        .parse()?;
+/// ## SectionValues
+///
+/// The values of the sections and the mount points which have a threshold
+/// or an alert, see `RwmStatus::get_value`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SectionValues {
+    /// The value of each section, unless it has none.
+    pub sections: Vec<(Section, f64)>,
+    /// The free space percentage of each mount point which can be read.
+    pub disks: Vec<(PathBuf, f64)>,
+    /// Whether a battery is discharging, only read if there is a battery
+    /// alert.
+    pub discharging: bool,
+}
 
-    let remaining_capacity: u64 = read_to_string(batt.join("charge_now"))
-        .or_else(|_| read_to_string(batt.join("energy_now")))?
-        .trim()
-        .parse()?;
+impl SectionValues {
+    /// Return the value of the provided section, if it has one.
+    pub fn get(&self, section: &Section) -> Option<f64> {
+        self.sections.iter().find(|(known, _)| known == section).map(|(_, value)| *value)
+    }
 
-    let status: char = match read_to_string(batt.join("status")) {
-        Ok(contents) => {
-            match &contents.trim()[..] {
-                "Full" => 'F',
-                "Discharging" => '-',
-                "Charging" => '+',
-                _ => '?',
-            }
+    /// Return the free space percentage of the provided mount point, if it
+    /// could be read.
+    pub fn disk(&self, mount: &Path) -> Option<f64> {
+        self.disks.iter().find(|(known, _)| known == mount).map(|(_, value)| *value)
+    }
+}
+
+/// Substitute every `{field}` placeholder in the template with the value
+/// returned by `resolve`.  Placeholders for which `resolve` returns `None` are
+/// left as they are.
+fn render_template<F>(template: &str, mut resolve: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        rendered.push_str(&rest[..start]);
+        match resolve(&rest[start + 1..end]) {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(&rest[start..=end]),
         }
-        Err(_) => '?',
-    };
+        rest = &rest[end + 1..];
+    }
 
-    let percentage = ((remaining_capacity as f64) / (design_capacity as f64)) * 100.0;
-    Ok(format!("{:.0}%{}", percentage, status))
+    rendered.push_str(rest);
+    rendered
 }
 
-/// Get the time for the provided timezone in the provided format.
-pub fn get_tz_time(tz_name: &str, fmt: &str) -> Result<String, StatusError> {
-    let tz: chrono_tz::Tz = tz_name.parse().map_err(StatusError::ParseTz)?;
-    let utc = Utc::now().naive_utc();
-    Ok(format!("{}", tz.from_utc_datetime(&utc).format(fmt)))
+/// ## Block
+///
+/// A single section of the status line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block {
+    /// The section name, see `Section::name`.
+    pub name: String,
+    /// The prefixed readout.
+    pub full_text: String,
+    /// The text colour, e.g. `#ff0000`, if it is not the bar's default.
+    pub color: Option<String>,
 }
 
-/// Get the local time.
-pub fn get_local_time(fmt: &str) -> String {
-    format!("{}", Local::now().format(fmt))
+impl Block {
+    /// Return the block as an i3bar protocol JSON object.
+    pub fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"name\":{},\"full_text\":{}",
+            json_string(&self.name),
+            json_string(&self.full_text)
+        );
+        if let Some(ref color) = self.color {
+            json.push_str(&format!(",\"color\":{}", json_string(color)));
+        }
+        json.push('}');
+        json
+    }
 }
 
-/// ## RwmStatus
+/// Quote and escape a string for use in JSON.
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// ## Readout
 ///
-/// This struct collects device dependent paths and user settings.  It also
-/// provides convenience methods to aggregate readouts.
-pub struct RwmStatus {
-    hw_mons: Vec<PathBuf>,
-    batts: Vec<PathBuf>,
-    tzs: Vec<Tz>,
+/// The result of reading a `Monitor`.
+pub type Readout = String;
+
+/// ## Monitor
+///
+/// A source of a single readout in the status line.  Implement it to add
+/// readouts to `RwmStatus` with `RwmStatus::add_monitor`.
+pub trait Monitor: Send + Sync {
+    /// Return the id of the monitor used to place it in the layout and in
+    /// templates, e.g. `cpu`.
+    fn id(&self) -> &str;
+
+    /// Read the monitor.
+    fn read(&self) -> Result<Readout, StatusError>;
+
+    /// Return how often the monitor should be read, `None` to read it on
+    /// every update.  See `RwmStatus::set_interval`.
+    fn interval(&self) -> Option<Duration> {
+        None
+    }
 }
 
-/// ## Tz
+/// ## LoadMonitor
 ///
-/// Holds the label and name of a time zone.
-struct Tz {
-    label: char,
-    name: String,
+/// Reads the three load averages.
+pub struct LoadMonitor {
+    id: String,
 }
 
-impl RwmStatus {
-    /// Build a new RwmStatus object.  This function collects all the monitor
-    /// and battery paths for later use.
-    pub fn new(tzs: &[(char, &str)]) -> RwmStatus {
-        RwmStatus {
-            hw_mons: RwmStatus::get_paths("/sys/devices/virtual/hwmon", "hwmon"),
-            batts: RwmStatus::get_paths("/sys/class/power_supply", "BAT"),
-            tzs: tzs.iter()
-                .map(|tz| {
-                    Tz {
-                        label: tz.0,
-                        name: String::from(tz.1),
-                    }
-                })
-                .collect(),
+impl LoadMonitor {
+    /// Build a new load average monitor.
+    pub fn new(id: &str) -> LoadMonitor {
+        LoadMonitor { id: String::from(id) }
+    }
+}
+
+impl Monitor for LoadMonitor {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn read(&self) -> Result<Readout, StatusError> {
+        get_load_avgs()
+    }
+}
+
+/// ## TempMonitor
+///
+/// Reads all the temperature sensors of a hwmon monitor.
+pub struct TempMonitor {
+    id: String,
+    hwmon: PathBuf,
+    unit: TempUnit,
+}
+
+impl TempMonitor {
+    /// Build a new temperature monitor for the provided hwmon directory.
+    pub fn new(id: &str, hwmon: &Path, unit: TempUnit) -> TempMonitor {
+        TempMonitor {
+            id: String::from(id),
+            hwmon: PathBuf::from(hwmon),
+            unit,
         }
     }
+}
 
-    /// Collect all the paths of the form base_path/prefix*
-    fn get_paths(base_path: &str, prefix: &str) -> Vec<PathBuf> {
-        let dir = match Path::new(base_path).read_dir() {
-            Ok(iter) => iter,
-            Err(_) => return vec![],
-        };
+impl Monitor for TempMonitor {
+    fn id(&self) -> &str {
+        &self.id
+    }
 
-        let dir_contents = dir.filter_map(|path_result| {
-            path_result.ok().and_then(|path| Some(path.path()))
-        });
+    fn read(&self) -> Result<Readout, StatusError> {
+        get_temp(&self.hwmon, self.unit)
+    }
+}
 
-        let mut paths: Vec<PathBuf> = dir_contents
-            .filter(|path| {
-                path.file_name()
-                    .and_then(|os_str| os_str.to_str())
-                    .filter(|entry| entry.starts_with(prefix))
-                    .is_some()
-            })
-            .collect();
+/// ## BatteryMonitor
+///
+/// Reads the charge percentage, status, and time remaining of a battery.
+pub struct BatteryMonitor {
+    id: String,
+    batt: PathBuf,
+}
 
-        paths.sort_unstable();
-        paths
+impl BatteryMonitor {
+    /// Build a new battery monitor for the provided battery directory.
+    pub fn new(id: &str, batt: &Path) -> BatteryMonitor {
+        BatteryMonitor {
+            id: String::from(id),
+            batt: PathBuf::from(batt),
+        }
     }
+}
 
-    /// Return temperature reads from all monitors.
-    pub fn get_temperatures(&self) -> Option<String> {
-        if self.hw_mons.is_empty() {
-            return None;
+impl Monitor for BatteryMonitor {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn read(&self) -> Result<Readout, StatusError> {
+        get_batt(&self.batt)
+    }
+}
+
+/// ## CpuMonitor
+///
+/// Reads the CPU usage since the previous readout, e.g. `37%`.  There is
+/// nothing to compare against on the first readout which is `--%`.
+pub struct CpuMonitor {
+    id: String,
+    prev: Mutex<Option<(u64, u64)>>,
+}
+
+impl CpuMonitor {
+    /// Build a new CPU usage monitor.
+    pub fn new(id: &str) -> CpuMonitor {
+        CpuMonitor {
+            id: String::from(id),
+            prev: Mutex::new(None),
         }
+    }
+}
 
-        let temp_strs: Vec<String> = self.hw_mons
-            .iter()
-            .map(|hw_mon| get_temp(&hw_mon).unwrap_or_else(|_| "".into()))
-            .collect();
-        Some(temp_strs.join("|"))
+impl Monitor for CpuMonitor {
+    fn id(&self) -> &str {
+        &self.id
     }
 
-    /// Return the three load average values.
-    #[inline]
-    pub fn get_load_avgs(&self) -> String {
-        get_load_avgs().unwrap_or_else(|_| "".into())
+    fn read(&self) -> Result<Readout, StatusError> {
+        let curr = get_cpu_times()?;
+        let prev = self.prev.lock().unwrap().replace(curr);
+        Ok(format_cpu_usage(prev.and_then(|prev| cpu_usage(prev, curr))))
     }
+}
 
-    /// Return battery status for all batteries.
-    pub fn get_batteries(&self) -> Option<String> {
-        if self.batts.is_empty() {
-            return None;
+/// ## MemoryMonitor
+///
+/// Reads the memory and swap usage, e.g. `5.2G/15.5G S:0.3G`.  The swap
+/// usage is left out if there is no swap.
+pub struct MemoryMonitor {
+    id: String,
+}
+
+impl MemoryMonitor {
+    /// Build a new memory monitor.
+    pub fn new(id: &str) -> MemoryMonitor {
+        MemoryMonitor { id: String::from(id) }
+    }
+}
+
+impl Monitor for MemoryMonitor {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn read(&self) -> Result<Readout, StatusError> {
+        let mem = get_mem_raw()?;
+        Ok(match mem.swap_total {
+            0 => mem.to_string(),
+            _ => format!("{} S:{}", mem, human_size(mem.swap_used)),
+        })
+    }
+}
+
+/// ## DiskMonitor
+///
+/// Reads the available space of file systems, e.g. `/:23.0G ~:412.0G`.
+pub struct DiskMonitor {
+    id: String,
+    mounts: Vec<PathBuf>,
+}
+
+impl DiskMonitor {
+    /// Build a new disk monitor for the provided mount points.
+    pub fn new(id: &str, mounts: &[&str]) -> DiskMonitor {
+        DiskMonitor {
+            id: String::from(id),
+            mounts: mounts.iter().map(PathBuf::from).collect(),
         }
+    }
+}
 
-        let batt_strs: Vec<String> = self.batts
-            .iter()
-            .map(|batt| get_batt(&batt).unwrap_or_else(|_| "".into()))
-            .collect();
-        Some(batt_strs.join("|"))
+impl Monitor for DiskMonitor {
+    fn id(&self) -> &str {
+        &self.id
     }
 
-    /// Return times for all configured time zones.
-    pub fn get_times(&self) -> String {
-        let mut tz_strs: Vec<String> = self.tzs
+    fn read(&self) -> Result<Readout, StatusError> {
+        let disk_strs = self.mounts
             .iter()
-            .map(|tz| {
-                format!(
-                    "{}:{}",
-                    tz.label,
-                    get_tz_time(&tz.name, "%H:%M").unwrap_or_else(|_| "".into())
-                )
-            })
-            .collect();
-        tz_strs.push(get_local_time("KW %W %a %d %b %H:%M %Z %Y"));
-        tz_strs.join(" ")
+            .map(|mount| get_disk(mount))
+            .collect::<Result<Vec<String>, StatusError>>()?;
+        Ok(disk_strs.join(" "))
+    }
+}
+
+/// ## TimeMonitor
+///
+/// Reads the time in a time zone or the local time.
+pub struct TimeMonitor {
+    id: String,
+    tz: Option<String>,
+    format: String,
+}
+
+impl TimeMonitor {
+    /// Build a new time monitor for the provided time zone, `None` for the
+    /// local time, with the provided `chrono` format.
+    pub fn new(id: &str, tz: Option<&str>, format: &str) -> TimeMonitor {
+        TimeMonitor {
+            id: String::from(id),
+            tz: tz.map(String::from),
+            format: String::from(format),
+        }
+    }
+}
+
+impl Monitor for TimeMonitor {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn read(&self) -> Result<Readout, StatusError> {
+        match self.tz {
+            Some(ref tz) => get_tz_time(tz, &self.format),
+            None => Ok(get_local_time(&self.format)),
+        }
+    }
+}
+
+/// ## CommandMonitor
+///
+/// Reads the first line printed by a shell command, e.g. a script counting
+/// the pending package updates.  The command runs in the background once per
+/// interval so that a slow command never holds up the status line, see
+/// `fetch_in_background`, and is killed if it outlasts its timeout.  Its
+/// output is shown from the next update on, reading the monitor in between
+/// only returns the last output.  A failed run is an error until the command
+/// runs again.
+pub struct CommandMonitor {
+    id: String,
+    command: String,
+    interval: Duration,
+    timeout: Duration,
+    /// The first line of the last run or the reason it failed.
+    output: Arc<Mutex<Fetched<Result<String, String>>>>,
+}
+
+impl CommandMonitor {
+    /// Build a new monitor which runs the provided shell command every
+    /// interval.
+    pub fn new(id: &str, command: &str, interval: Duration, timeout: Duration) -> CommandMonitor {
+        CommandMonitor {
+            id: String::from(id),
+            command: String::from(command),
+            interval,
+            timeout,
+            output: Arc::new(Mutex::new(Fetched::default())),
+        }
+    }
+}
+
+impl Monitor for CommandMonitor {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn read(&self) -> Result<Readout, StatusError> {
+        let (output, _) = fetch_in_background(&self.output, self.interval, {
+            let command = self.command.clone();
+            let timeout = self.timeout;
+            move || Ok(run_shell(&command, timeout))
+        });
+        match output {
+            Some(Ok(line)) => Ok(line),
+            Some(Err(reason)) => Err(StatusError::Command(self.command.clone(), reason)),
+            None => Ok(String::new()),
+        }
+    }
+}
+
+/// ## StatusProvider
+///
+/// A source of status lines for a status bar.  Bar frontends can depend on
+/// this trait rather than on a concrete status collector.
+pub trait StatusProvider {
+    /// Return the current status line.
+    fn status(&mut self) -> String;
+}
+
+impl StatusProvider for RwmStatus {
+    fn status(&mut self) -> String {
+        self.get_status()
     }
 }
 
@@ -196,9 +4646,21 @@ impl RwmStatus {
 pub enum StatusError {
     Io(std::io::Error),
     ParseNum(std::num::ParseIntError),
+    ParseFloat(std::num::ParseFloatError),
     ParseTz(String),
     NotPresent(String),
     System(i32),
+    InvalidArg(String),
+    /// Reading or parsing the file at the path failed, e.g. a sysfs
+    /// attribute.
+    File(PathBuf, Box<StatusError>),
+    /// The external command failed for the reason given, e.g. `exited with
+    /// 1`.
+    Command(String, String),
+    /// A netlink request failed.
+    Netlink(std::io::Error),
+    /// NVML returned the error code.
+    Nvml(i32),
 }
 
 impl std::fmt::Display for StatusError {
@@ -206,21 +4668,27 @@ impl std::fmt::Display for StatusError {
         match self {
             StatusError::Io(ioe) => ioe.fmt(f),
             StatusError::ParseNum(pie) => pie.fmt(f),
+            StatusError::ParseFloat(pfe) => pfe.fmt(f),
             StatusError::ParseTz(s) => write!(f, "{}", s),
             StatusError::NotPresent(s) => write!(f, "{} not present", s),
             StatusError::System(i) => write!(f, "System call returned {}", i),
+            StatusError::InvalidArg(s) => write!(f, "Invalid argument: {}", s),
+            StatusError::File(path, err) => write!(f, "{}: {}", path.display(), err),
+            StatusError::Command(command, reason) => write!(f, "{}: {}", command, reason),
+            StatusError::Netlink(ioe) => write!(f, "netlink: {}", ioe),
+            StatusError::Nvml(rc) => write!(f, "NVML returned {}", rc),
         }
     }
 }
 
 impl std::error::Error for StatusError {
-    fn description(&self) -> &str {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            StatusError::Io(ioe) => ioe.description(),
-            StatusError::ParseNum(pie) => pie.description(),
-            StatusError::ParseTz(_) => "Invalid timezone",
-            StatusError::NotPresent(_) => "Device not present",
-            StatusError::System(_) => "System call returned error",
+            StatusError::Io(ioe) | StatusError::Netlink(ioe) => Some(ioe),
+            StatusError::ParseNum(pie) => Some(pie),
+            StatusError::ParseFloat(pfe) => Some(pfe),
+            StatusError::File(_, err) => Some(err.as_ref()),
+            _ => None,
         }
     }
 }
@@ -236,3 +4704,46 @@ impl From<std::num::ParseIntError> for StatusError {
         StatusError::ParseNum(err)
     }
 }
+
+impl From<std::num::ParseFloatError> for StatusError {
+    fn from(err: std::num::ParseFloatError) -> Self {
+        StatusError::ParseFloat(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn human_size_units() {
+        assert_eq!(human_size(0), "0B");
+        assert_eq!(human_size(1023), "1023B");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(3 * GIB / 2 - 1), "1.5G");
+        assert_eq!(human_size(3 * GIB / 2), "1.5G");
+        assert_eq!(human_size(3 * GIB / 2 + GIB / 10), "1.6G");
+        // There is no unit beyond terabytes.
+        assert_eq!(human_size(2048 * 1024 * GIB), "2048.0T");
+    }
+
+    #[test]
+    fn net_rates_between_samples() {
+        assert_eq!(
+            net_rates((1_000, 5_000), (1_000 + 2 * 1258291, 5_000 + 2 * 65536), 2.0).as_deref(),
+            Some("↓1.2M ↑64.0K")
+        );
+        assert_eq!(
+            net_rates((1_000, 5_000), (1_512, 5_000), 0.5).as_deref(),
+            Some("↓1.0K ↑0B")
+        );
+        // A counter which was reset counts as no traffic rather than a spike.
+        assert_eq!(
+            net_rates((u64::from(u32::MAX), 5_000), (10, 6_024), 1.0).as_deref(),
+            Some("↓0B ↑1.0K")
+        );
+        assert_eq!(net_rates((1_000, 5_000), (2_000, 6_000), 0.0), None);
+    }
+}
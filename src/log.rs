@@ -0,0 +1,136 @@
+//! # Logging
+//!
+//! A minimal logging facade: the `error!`, `warn!`, `info!`, and `debug!`
+//! macros write messages at or above the configured level to stderr or, once
+//! `init` has been called with `Target::Syslog`, to syslog.  Warnings and
+//! errors are logged until `init` says otherwise.
+
+// std imports
+use std::ffi::CString;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// The most verbose level which is logged.
+static LEVEL: AtomicUsize = AtomicUsize::new(Level::Warn as usize);
+
+/// Set if messages are logged to syslog rather than to stderr.
+static SYSLOG: AtomicBool = AtomicBool::new(false);
+
+/// ## Level
+///
+/// The severity of a message, from the most to the least severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    /// Return the priority of the level for `syslog(3)`.
+    fn priority(self) -> libc::c_int {
+        match self {
+            Level::Error => libc::LOG_ERR,
+            Level::Warn => libc::LOG_WARNING,
+            Level::Info => libc::LOG_INFO,
+            Level::Debug => libc::LOG_DEBUG,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Level::Error => "error",
+            Level::Warn => "warning",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        })
+    }
+}
+
+/// ## Target
+///
+/// Where messages are logged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Stderr,
+    /// The system logger, with the `daemon` facility.
+    Syslog,
+}
+
+/// Log the messages at or above the provided level to the provided target.
+pub fn init(target: Target, level: Level) {
+    LEVEL.store(level as usize, Ordering::SeqCst);
+    if target == Target::Syslog {
+        // The identifier must outlive every later call to syslog.
+        unsafe {
+            libc::openlog(
+                b"rwmstatus\0".as_ptr() as *const libc::c_char,
+                libc::LOG_PID,
+                libc::LOG_DAEMON,
+            );
+        }
+    }
+    SYSLOG.store(target == Target::Syslog, Ordering::SeqCst);
+}
+
+/// Return true if messages at the provided level are logged.
+pub fn enabled(level: Level) -> bool {
+    level as usize <= LEVEL.load(Ordering::SeqCst)
+}
+
+/// Log a message at the provided level, see the logging macros.
+pub fn log(level: Level, args: fmt::Arguments) {
+    if !enabled(level) {
+        return;
+    }
+
+    if SYSLOG.load(Ordering::SeqCst) {
+        let message = CString::new(args.to_string().replace('\0', "")).unwrap_or_default();
+        unsafe {
+            libc::syslog(
+                level.priority(),
+                b"%s\0".as_ptr() as *const libc::c_char,
+                message.as_ptr(),
+            );
+        }
+    } else if level == Level::Error {
+        eprintln!("rwmstatus: {}", args);
+    } else {
+        eprintln!("rwmstatus: {}: {}", level, args);
+    }
+}
+
+/// Log an error, e.g. `error!("cannot write to {}: {}", name, err)`.
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => {
+        $crate::log::log($crate::log::Level::Error, format_args!($($arg)+))
+    };
+}
+
+/// Log a warning, e.g. about a failed readout.
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => {
+        $crate::log::log($crate::log::Level::Warn, format_args!($($arg)+))
+    };
+}
+
+/// Log an informational message.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => {
+        $crate::log::log($crate::log::Level::Info, format_args!($($arg)+))
+    };
+}
+
+/// Log a debugging message.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        $crate::log::log($crate::log::Level::Debug, format_args!($($arg)+))
+    };
+}
@@ -11,58 +11,339 @@
 
 // Lib import
 extern crate rwmstatus;
+use rwmstatus::log::{self, Level, Target};
 use rwmstatus::*;
 
 // External crates
+extern crate libc;
+#[cfg(feature = "x11")]
 extern crate x11;
 
 // std imports
-use std::ffi::CString;
-
-// x11 imports
-use x11::xlib::{Display, XDefaultRootWindow, XOpenDisplay, XStoreName, XSync};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 // Internal module imports
+mod args;
 mod config;
+mod events;
+mod fifo;
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "notify")]
+mod notify;
+mod signal;
+mod sink;
+#[cfg(feature = "systemd")]
+mod systemd;
+mod toml;
+
+use config::{Config, Output};
+use events::Event;
+use sink::OutputSink;
 
 fn main() {
-    let display: *mut Display;
+    let args = args::Args::parse();
+    let level = if args.verbose { Level::Debug } else { Level::Warn };
+    log::init(if args.syslog { Target::Syslog } else { Target::Stderr }, level);
+
+    let (mut config, mut rwmstatus) = match load(&args) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            error!("{}", err);
+            std::process::exit(1);
+        }
+    };
 
-    unsafe {
-        display = XOpenDisplay(std::ptr::null());
+    if args.self_test {
+        std::process::exit(self_test(&rwmstatus));
     }
 
-    if display.is_null() {
-        eprintln!("rwmstatus: cannot open display.");
-        std::process::exit(1);
+    let mut sinks = match open_sinks(&config, rwmstatus.format(), &mut vec![]) {
+        Ok(sinks) => sinks,
+        Err(err) => {
+            error!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    #[cfg(feature = "http")]
+    let json = std::sync::Arc::new(std::sync::Mutex::new(String::from("{}")));
+    #[cfg(feature = "http")]
+    {
+        if let Err(err) = http::serve(config::HTTP_ADDR, json.clone()) {
+            error!("cannot listen on {}: {}", config::HTTP_ADDR, err);
+            std::process::exit(1);
+        }
     }
 
-    let rwmstatus = RwmStatus::new(&config::TZS[..]);
+    signal::install();
 
-    let mut stats = vec![];
-    loop {
-        if let Some(temps) = rwmstatus.get_temperatures() {
-            stats.push(format!("T:{}", temps));
+    // The X display, if any, is open so the service is up.
+    #[cfg(feature = "systemd")]
+    let mut watchdog = systemd::Watchdog::new();
+    #[cfg(feature = "systemd")]
+    {
+        if let Err(err) = systemd::notify("READY=1") {
+            warn!("failed to notify systemd: {}", err);
         }
+    }
 
-        let avgs = rwmstatus.get_load_avgs();
-        stats.push(format!("L:{}", avgs));
+    let mut events = events::Events::new();
+    let config_path = args.config.as_ref().map(PathBuf::from).or_else(Config::default_path);
+    if let Some(ref path) = config_path {
+        if let Err(err) = events.watch(path) {
+            info!("cannot watch {}: {}", path.display(), err);
+        }
+    }
+    watch_maildirs(&mut events, &rwmstatus);
+    #[cfg(feature = "upower")]
+    {
+        if config.batt_backend == BattBackend::Upower {
+            let mut upower = std::process::Command::new("upower");
+            if let Err(err) = events.monitor(upower.arg("--monitor"), "power_supply") {
+                info!("cannot monitor UPower: {}", err);
+            }
+        }
+    }
+
+    while !signal::terminated() {
+        #[cfg(feature = "systemd")]
+        let start = Instant::now();
+        let snapshot = rwmstatus.collect_all();
+        let status = snapshot.format(rwmstatus.format());
 
-        if let Some(batts) = rwmstatus.get_batteries() {
-            stats.push(format!("B:{}", batts));
+        #[cfg(feature = "http")]
+        {
+            *json.lock().unwrap() = snapshot.to_json();
         }
 
-        let times = rwmstatus.get_times();
-        stats.push(times);
+        #[cfg(feature = "systemd")]
+        {
+            let render_time = start.elapsed().as_millis();
+            let state = format!("STATUS=rendered in {}ms: {}", render_time, status);
+            if let Err(err) = systemd::notify(&state).and_then(|_| watchdog.ping()) {
+                warn!("failed to notify systemd: {}", err);
+            }
+        }
 
-        let status = CString::new(stats.join(" ")).expect("Failed to create status CString.");
-        unsafe {
-            XStoreName(display, XDefaultRootWindow(display), status.as_ptr());
-            XSync(display, false as i32);
+        for (_, sink) in &mut sinks {
+            if let Err(err) = sink.write(&snapshot, &status) {
+                error!("cannot write to {}: {}", sink.name(), err);
+            }
         }
 
-        std::thread::sleep(std::time::Duration::from_secs(60));
+        #[cfg(feature = "notify")]
+        for message in rwmstatus.take_alerts(&snapshot) {
+            if let Err(err) = notify::send(&message) {
+                warn!("cannot show the alert {}: {}", message, err);
+            }
+        }
+
+        if args.once {
+            break;
+        }
+
+        let interval = Duration::from_secs(config.interval);
+        let mut deadline = Instant::now() + rwmstatus.next_update().unwrap_or(interval);
+        let mut rescan = false;
+        let mut reload = false;
+        loop {
+            // The watchdog is pinged while waiting, so that it only fires if
+            // reading the status hangs.
+            #[cfg(feature = "systemd")]
+            let wake = watchdog.deadline().map_or(deadline, |ping| ping.min(deadline));
+            #[cfg(not(feature = "systemd"))]
+            let wake = deadline;
+            let event = match events.wait(wake.saturating_duration_since(Instant::now())) {
+                Some(event) => event,
+                None if Instant::now() < deadline && !signal::pending() => {
+                    #[cfg(feature = "systemd")]
+                    {
+                        if let Err(err) = watchdog.ping() {
+                            warn!("failed to notify systemd: {}", err);
+                        }
+                    }
+                    continue;
+                }
+                None => break,
+            };
+            match event {
+                Event::Uevent { action, subsystem } => {
+                    let sections = match subsystem.as_str() {
+                        "power_supply" => vec![Section::Batteries],
+                        "backlight" => vec![Section::Brightness],
+                        "net" => vec![Section::Network, Section::Wifi, Section::Vpn],
+                        "hwmon" => vec![Section::Temperatures, Section::Gpu],
+                        _ => continue,
+                    };
+                    // Devices are only discovered when the status is built
+                    // and their static attributes are only read once.
+                    // Network interfaces are looked up on every readout.
+                    if (action == "add" || action == "remove") && subsystem != "net" {
+                        rescan = true;
+                    }
+                    for section in &sections {
+                        rwmstatus.invalidate(section);
+                    }
+                }
+                Event::Changed(ref path) if Some(path) == config_path.as_ref() => reload = true,
+                // Anything else is the new mail of a Maildir.
+                Event::Changed(_) => rwmstatus.invalidate(&Section::Mail),
+            }
+            deadline = deadline.min(Instant::now() + events::SETTLE_TIME);
+        }
+
+        for refresh in signal::take_refresh() {
+            match refresh {
+                None => rwmstatus.invalidate_all(),
+                Some(n) => {
+                    if let Some((section, _)) = rwmstatus.format().sections.get(n - 1) {
+                        rwmstatus.invalidate(section);
+                    }
+                }
+            }
+        }
 
-        stats.clear();
+        if reload || signal::take_reload() {
+            match load(&args) {
+                Ok(loaded) => {
+                    // The alerts which are still raised are not shown again.
+                    loaded.1.inherit_alerts(&rwmstatus);
+                    config = loaded.0;
+                    rwmstatus = loaded.1;
+                    rescan = false;
+                    match open_sinks(&config, rwmstatus.format(), &mut sinks) {
+                        Ok(reopened) => sinks = reopened,
+                        Err(err) => error!("cannot change the outputs: {}", err),
+                    }
+                    for (_, sink) in &mut sinks {
+                        sink.set_format(rwmstatus.format());
+                    }
+                    watch_maildirs(&mut events, &rwmstatus);
+                    info!("reloaded the configuration");
+                }
+                Err(err) => error!("cannot reload the configuration: {}", err),
+            }
+        }
+        if rescan {
+            rwmstatus.rescan();
+        }
+    }
+
+    // The status is meant to stay after a single update.
+    for (_, sink) in &mut sinks {
+        sink.close(args.once);
+    }
+}
+
+/// Load the configuration, apply the command line options, and build the
+/// status from it.
+fn load(args: &args::Args) -> Result<(Config, RwmStatus), String> {
+    let mut config = Config::load(args.config.as_ref().map(Path::new))?;
+    args.apply(&mut config);
+
+    let rwmstatus = config.status();
+    if let Some(ref template) = rwmstatus.format().template {
+        rwmstatus
+            .check_template(template)
+            .map_err(|err| format!("invalid template: {}", err))?;
+    }
+    Ok((config, rwmstatus))
+}
+
+/// What a sink writes to, to tell which sinks can be kept when the
+/// configuration is reloaded.
+#[derive(Clone, PartialEq)]
+enum SinkTarget {
+    /// One of the configured outputs.
+    Output(Output),
+    /// The FIFO at the configured path.
+    Fifo(PathBuf),
+}
+
+/// The sinks the status is written to with what they write to.
+type Sinks = Vec<(SinkTarget, Box<dyn OutputSink>)>;
+
+/// Open a sink for each of the configured outputs and for the FIFO, if there
+/// is one.  The `previous` sinks which write to the same place are kept, e.g.
+/// the X11 display, and the rest are closed.  The `previous` sinks are left
+/// alone if a new sink cannot be opened.
+fn open_sinks(
+    config: &Config,
+    format: &StatusFormat,
+    previous: &mut Sinks,
+) -> Result<Sinks, String> {
+    let outputs = if config.outputs.is_empty() {
+        vec![Output::detect()]
+    } else {
+        config.outputs.clone()
+    };
+    let mut targets: Vec<SinkTarget> = vec![];
+    let fifo = config.fifo.as_ref().map(|path| SinkTarget::Fifo(PathBuf::from(path)));
+    for target in outputs.into_iter().map(SinkTarget::Output).chain(fifo) {
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+
+    let mut opened: Sinks = vec![];
+    for target in &targets {
+        if previous.iter().all(|(kept, _)| kept != target) {
+            opened.push((target.clone(), open_sink(target, format)?));
+        }
+    }
+
+    let mut sinks = vec![];
+    for target in targets {
+        // The new sinks were opened in the same order.
+        match previous.iter().position(|(kept, _)| *kept == target) {
+            Some(index) => sinks.push(previous.remove(index)),
+            None => sinks.push(opened.remove(0)),
+        }
+    }
+    for (_, mut sink) in previous.drain(..) {
+        sink.close(false);
+    }
+    Ok(sinks)
+}
+
+/// Open a sink which writes to the provided target.
+fn open_sink(target: &SinkTarget, format: &StatusFormat) -> Result<Box<dyn OutputSink>, String> {
+    Ok(match target {
+        #[cfg(feature = "x11")]
+        SinkTarget::Output(Output::X11) => Box::new(sink::X11Sink::open()?),
+        // Without X11 support the status can only be printed to stdout.
+        #[cfg(not(feature = "x11"))]
+        SinkTarget::Output(Output::X11) => Box::new(sink::StdoutSink),
+        SinkTarget::Output(Output::Stdout) => Box::new(sink::StdoutSink),
+        SinkTarget::Output(Output::I3bar) => Box::new(sink::I3barSink::new(format.clone())),
+        SinkTarget::Fifo(path) => Box::new(fifo::Fifo::new(path)),
+    })
+}
+
+/// Watch the `new` directories of the Maildirs so that mail shows up as soon
+/// as it arrives.
+fn watch_maildirs(events: &mut events::Events, rwmstatus: &RwmStatus) {
+    for maildir in rwmstatus.maildirs() {
+        let new = maildir.join("new");
+        if let Err(err) = events.watch_dir(&new) {
+            info!("cannot watch {}: {}", new.display(), err);
+        }
+    }
+}
+
+/// Print the readout of every discovered device and return the exit code.
+fn self_test(rwmstatus: &RwmStatus) -> i32 {
+    let mut rc = 0;
+    for (device, result) in rwmstatus.self_test() {
+        match result {
+            Ok(readout) => println!("{}: {}", device, readout),
+            Err(err) => {
+                println!("{}: FAILED: {}", device, err);
+                rc = 1;
+            }
+        }
     }
+    rc
 }
@@ -0,0 +1,226 @@
+//! # nl80211
+//!
+//! A minimal generic netlink client for the nl80211 interface which replaced
+//! the wireless extensions.  It only asks for the SSID an interface is
+//! connected to and for the signal strength of its access point.
+
+// std imports
+use std::ffi::CString;
+use std::io;
+use std::mem;
+use std::os::unix::io::RawFd;
+
+// Internal imports
+use StatusError;
+
+/// Protocol of the generic netlink sockets.
+const NETLINK_GENERIC: libc::c_int = 16;
+
+/// The generic netlink controller which resolves family names to ids.
+const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+const NL80211_CMD_GET_INTERFACE: u8 = 5;
+const NL80211_CMD_GET_STATION: u8 = 17;
+const NL80211_ATTR_IFINDEX: u16 = 3;
+const NL80211_ATTR_STA_INFO: u16 = 21;
+const NL80211_ATTR_SSID: u16 = 52;
+const NL80211_STA_INFO_SIGNAL: u16 = 7;
+
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_DUMP: u16 = 0x300;
+
+/// Size of `struct nlmsghdr`.
+const NLMSG_HDRLEN: usize = 16;
+/// Size of `struct genlmsghdr`.
+const GENL_HDRLEN: usize = 4;
+/// Size of `struct nlattr`.
+const NLA_HDRLEN: usize = 4;
+
+/// Size of the receive buffer, enough for a station dump of a single link.
+const BUF_SIZE: usize = 16384;
+
+/// Return the SSID the provided interface is connected to and the signal
+/// strength of the access point in dBm, if known, or `None` if the interface
+/// is not connected.
+pub fn get_link(iface: &str) -> Result<Option<(String, Option<i8>)>, StatusError> {
+    let name = CString::new(iface).map_err(|_| StatusError::InvalidArg(String::from(iface)))?;
+    let ifindex = unsafe { libc::if_nametoindex(name.as_ptr()) };
+    if ifindex == 0 {
+        return Err(StatusError::NotPresent(String::from(iface)));
+    }
+
+    let socket = Socket::open()?;
+    let family = socket.family_id("nl80211")?;
+    let ifindex = attr(NL80211_ATTR_IFINDEX, &ifindex.to_ne_bytes());
+
+    let ssid = socket
+        .request(family, NL80211_CMD_GET_INTERFACE, 0, &ifindex)?
+        .iter()
+        .find_map(|msg| find_attr(msg, NL80211_ATTR_SSID).map(<[u8]>::to_vec));
+    let ssid = match ssid {
+        Some(ssid) => String::from_utf8_lossy(&ssid).into_owned(),
+        None => return Ok(None),
+    };
+
+    let signal = socket
+        .request(family, NL80211_CMD_GET_STATION, NLM_F_DUMP, &ifindex)?
+        .iter()
+        .filter_map(|msg| find_attr(msg, NL80211_ATTR_STA_INFO))
+        .find_map(|info| find_attr(info, NL80211_STA_INFO_SIGNAL))
+        .and_then(|signal| signal.first())
+        .map(|signal| *signal as i8);
+    Ok(Some((ssid, signal)))
+}
+
+/// A generic netlink socket.
+struct Socket(RawFd);
+
+impl Socket {
+    fn open() -> Result<Socket, StatusError> {
+        let fd = unsafe {
+            libc::socket(libc::AF_NETLINK, libc::SOCK_RAW | libc::SOCK_CLOEXEC, NETLINK_GENERIC)
+        };
+        if fd < 0 {
+            return Err(StatusError::Netlink(io::Error::last_os_error()));
+        }
+        // Dropping the socket closes it if binding fails.
+        let socket = Socket(fd);
+
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        let rc = unsafe {
+            libc::bind(
+                fd,
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if rc < 0 {
+            return Err(StatusError::Netlink(io::Error::last_os_error()));
+        }
+        Ok(socket)
+    }
+
+    /// Resolve the id of the generic netlink family with the provided name.
+    fn family_id(&self, name: &str) -> Result<u16, StatusError> {
+        let mut family = Vec::from(name.as_bytes());
+        family.push(0);
+        let attrs = attr(CTRL_ATTR_FAMILY_NAME, &family);
+
+        self.request(GENL_ID_CTRL, CTRL_CMD_GETFAMILY, 0, &attrs)?
+            .iter()
+            .find_map(|msg| find_attr(msg, CTRL_ATTR_FAMILY_ID))
+            .filter(|id| id.len() >= 2)
+            .map(|id| u16::from_ne_bytes([id[0], id[1]]))
+            .ok_or_else(|| StatusError::NotPresent(format!("{} netlink family", name)))
+    }
+
+    /// Send a request and return the attributes of each message of the
+    /// reply.  Dumps are read until their end.
+    fn request(
+        &self,
+        family: u16,
+        cmd: u8,
+        flags: u16,
+        attrs: &[u8],
+    ) -> Result<Vec<Vec<u8>>, StatusError> {
+        let len = NLMSG_HDRLEN + GENL_HDRLEN + attrs.len();
+        let mut msg = Vec::with_capacity(len);
+        msg.extend_from_slice(&(len as u32).to_ne_bytes());
+        msg.extend_from_slice(&family.to_ne_bytes());
+        msg.extend_from_slice(&(NLM_F_REQUEST | flags).to_ne_bytes());
+        msg.extend_from_slice(&1u32.to_ne_bytes()); // sequence number
+        msg.extend_from_slice(&0u32.to_ne_bytes()); // port id, set by the kernel
+        msg.extend_from_slice(&[cmd, 1, 0, 0]); // command, version, reserved
+        msg.extend_from_slice(attrs);
+
+        let rc = unsafe { libc::send(self.0, msg.as_ptr() as *const libc::c_void, msg.len(), 0) };
+        if rc < 0 {
+            return Err(StatusError::Netlink(io::Error::last_os_error()));
+        }
+
+        let mut replies = vec![];
+        let mut buf = vec![0u8; BUF_SIZE];
+        loop {
+            let len =
+                unsafe { libc::recv(self.0, buf.as_mut_ptr() as *mut libc::c_void, BUF_SIZE, 0) };
+            if len < 0 {
+                return Err(StatusError::Netlink(io::Error::last_os_error()));
+            }
+
+            let mut data = &buf[..len as usize];
+            while data.len() >= NLMSG_HDRLEN {
+                let msg_len = u32::from_ne_bytes([data[0], data[1], data[2], data[3]]) as usize;
+                let msg_type = u16::from_ne_bytes([data[4], data[5]]);
+                if msg_len < NLMSG_HDRLEN || msg_len > data.len() {
+                    break;
+                }
+
+                let payload = &data[NLMSG_HDRLEN..msg_len];
+                match msg_type {
+                    NLMSG_DONE => return Ok(replies),
+                    NLMSG_ERROR => {
+                        let errno = payload
+                            .get(..4)
+                            .map_or(0, |e| i32::from_ne_bytes([e[0], e[1], e[2], e[3]]));
+                        if errno != 0 {
+                            return Err(StatusError::Netlink(io::Error::from_raw_os_error(-errno)));
+                        }
+                    }
+                    _ => replies.push(payload.get(GENL_HDRLEN..).unwrap_or(&[]).to_vec()),
+                }
+                data = &data[align(msg_len).min(data.len())..];
+            }
+
+            if flags & NLM_F_DUMP == 0 {
+                return Ok(replies);
+            }
+        }
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+/// Round up to the 4-byte alignment of netlink messages and attributes.
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// Encode a netlink attribute.
+fn attr(kind: u16, payload: &[u8]) -> Vec<u8> {
+    let len = NLA_HDRLEN + payload.len();
+    let mut attr = Vec::with_capacity(align(len));
+    attr.extend_from_slice(&(len as u16).to_ne_bytes());
+    attr.extend_from_slice(&kind.to_ne_bytes());
+    attr.extend_from_slice(payload);
+    attr.resize(align(len), 0);
+    attr
+}
+
+/// Return the payload of the first attribute of the provided kind.
+fn find_attr(mut attrs: &[u8], kind: u16) -> Option<&[u8]> {
+    while attrs.len() >= NLA_HDRLEN {
+        let len = u16::from_ne_bytes([attrs[0], attrs[1]]) as usize;
+        // The top bits flag nested and byte-swapped attributes.
+        let attr_kind = u16::from_ne_bytes([attrs[2], attrs[3]]) & 0x3fff;
+        if len < NLA_HDRLEN || len > attrs.len() {
+            return None;
+        }
+        if attr_kind == kind {
+            return Some(&attrs[NLA_HDRLEN..len]);
+        }
+        attrs = &attrs[align(len).min(attrs.len())..];
+    }
+    None
+}
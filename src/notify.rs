@@ -0,0 +1,22 @@
+//! # Desktop notifications
+//!
+//! Shows the alerts raised by `RwmStatus::take_alerts` as desktop
+//! notifications with `notify-send`, which comes with libnotify and passes
+//! them on to the notification daemon over D-Bus.
+
+// std imports
+use std::io;
+use std::process::{Command, Stdio};
+
+/// Show a critical notification with the provided message.
+pub fn send(message: &str) -> io::Result<()> {
+    let status = Command::new("notify-send")
+        .args(["--app-name=rwmstatus", "--urgency=critical", "rwmstatus", message])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("notify-send failed with {}", status)));
+    }
+    Ok(())
+}
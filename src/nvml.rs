@@ -0,0 +1,143 @@
+//! # NVML
+//!
+//! Reads NVIDIA GPUs through the NVIDIA Management Library which comes with
+//! the proprietary driver.  The library is loaded at runtime so that the
+//! status monitor still runs on machines without the driver.
+
+// std imports
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+use std::ptr;
+
+// Internal imports
+use GpuReadout;
+use StatusError;
+
+const NVML_SUCCESS: c_int = 0;
+const NVML_TEMPERATURE_GPU: c_int = 0;
+const NVML_CLOCK_GRAPHICS: c_int = 0;
+const NVML_DEVICE_NAME_BUFFER_SIZE: usize = 96;
+
+/// An opaque `nvmlDevice_t`.
+type Device = *mut c_void;
+
+/// `nvmlUtilization_t`, both as percentages.
+#[repr(C)]
+struct Utilization {
+    gpu: c_uint,
+    memory: c_uint,
+}
+
+/// Read all the GPUs known to NVML.  Queries a GPU does not support are left
+/// out of its readout.
+pub fn get_gpus() -> Result<Vec<GpuReadout>, StatusError> {
+    let nvml = Library::open("libnvidia-ml.so.1")?;
+    unsafe {
+        let init: unsafe extern "C" fn() -> c_int = nvml.symbol("nvmlInit_v2")?;
+        let shutdown: unsafe extern "C" fn() -> c_int = nvml.symbol("nvmlShutdown")?;
+        check(init())?;
+        let gpus = read_gpus(&nvml);
+        shutdown();
+        gpus
+    }
+}
+
+/// Read all the GPUs of an initialised library.
+unsafe fn read_gpus(nvml: &Library) -> Result<Vec<GpuReadout>, StatusError> {
+    let get_count: unsafe extern "C" fn(*mut c_uint) -> c_int =
+        nvml.symbol("nvmlDeviceGetCount_v2")?;
+    let get_handle: unsafe extern "C" fn(c_uint, *mut Device) -> c_int =
+        nvml.symbol("nvmlDeviceGetHandleByIndex_v2")?;
+    let get_name: unsafe extern "C" fn(Device, *mut c_char, c_uint) -> c_int =
+        nvml.symbol("nvmlDeviceGetName")?;
+    let get_temp: unsafe extern "C" fn(Device, c_int, *mut c_uint) -> c_int =
+        nvml.symbol("nvmlDeviceGetTemperature")?;
+    let get_utilization: unsafe extern "C" fn(Device, *mut Utilization) -> c_int =
+        nvml.symbol("nvmlDeviceGetUtilizationRates")?;
+    let get_clock: unsafe extern "C" fn(Device, c_int, *mut c_uint) -> c_int =
+        nvml.symbol("nvmlDeviceGetClockInfo")?;
+    let get_power: unsafe extern "C" fn(Device, *mut c_uint) -> c_int =
+        nvml.symbol("nvmlDeviceGetPowerUsage")?;
+
+    let mut count = 0;
+    check(get_count(&mut count))?;
+    (0..count)
+        .map(|index| {
+            let mut device = ptr::null_mut();
+            check(get_handle(index, &mut device))?;
+
+            let mut name = [0 as c_char; NVML_DEVICE_NAME_BUFFER_SIZE];
+            let name = succeeded(get_name(device, name.as_mut_ptr(), name.len() as c_uint))
+                .map(|_| CStr::from_ptr(name.as_ptr()).to_string_lossy().into_owned());
+            let mut temp = 0;
+            let millidegrees = succeeded(get_temp(device, NVML_TEMPERATURE_GPU, &mut temp))
+                .map(|_| f64::from(temp) * 1000.0);
+            let mut utilization = Utilization { gpu: 0, memory: 0 };
+            let busy = succeeded(get_utilization(device, &mut utilization))
+                .map(|_| f64::from(utilization.gpu));
+            let mut clock = 0;
+            let clock = succeeded(get_clock(device, NVML_CLOCK_GRAPHICS, &mut clock))
+                .map(|_| u64::from(clock));
+            let mut milliwatts = 0;
+            let power = succeeded(get_power(device, &mut milliwatts))
+                .map(|_| f64::from(milliwatts) / 1000.0);
+
+            Ok(GpuReadout {
+                card: format!("nvidia{}", index),
+                name,
+                millidegrees,
+                busy,
+                clock,
+                power,
+            })
+        })
+        .collect()
+}
+
+/// Turn an NVML return code into an error.
+fn check(rc: c_int) -> Result<(), StatusError> {
+    succeeded(rc).ok_or(StatusError::Nvml(rc))
+}
+
+/// Return `Some` if the NVML return code is a success.
+fn succeeded(rc: c_int) -> Option<()> {
+    if rc == NVML_SUCCESS {
+        Some(())
+    } else {
+        None
+    }
+}
+
+/// A dynamically loaded library.
+struct Library(*mut c_void);
+
+impl Library {
+    fn open(name: &str) -> Result<Library, StatusError> {
+        let filename = CString::new(name).map_err(|_| StatusError::InvalidArg(name.into()))?;
+        let handle = unsafe { libc::dlopen(filename.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            return Err(StatusError::NotPresent(String::from(name)));
+        }
+        Ok(Library(handle))
+    }
+
+    /// Look up the function with the provided name.  The caller must provide
+    /// its correct signature as `T`.
+    unsafe fn symbol<T: Copy>(&self, name: &str) -> Result<T, StatusError> {
+        let symbol = CString::new(name).map_err(|_| StatusError::InvalidArg(name.into()))?;
+        let address = libc::dlsym(self.0, symbol.as_ptr());
+        if address.is_null() {
+            return Err(StatusError::NotPresent(format!("{} in NVML", name)));
+        }
+        Ok(mem::transmute_copy(&address))
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        unsafe {
+            libc::dlclose(self.0);
+        }
+    }
+}
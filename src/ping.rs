@@ -0,0 +1,148 @@
+//! # Ping
+//!
+//! Sends ICMP echo requests over the unprivileged ICMP sockets of Linux, so
+//! no raw sockets or capabilities are needed.  They are only permitted to the
+//! groups in `net.ipv4.ping_group_range`.
+
+// std imports
+use std::io;
+use std::mem;
+use std::net::{IpAddr, SocketAddr};
+use std::os::unix::io::RawFd;
+use std::time::{Duration, Instant};
+
+const ICMP_ECHO_REQUEST: u8 = 8;
+const ICMP_ECHO_REPLY: u8 = 0;
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+const ICMPV6_ECHO_REPLY: u8 = 129;
+
+/// Size of an ICMP echo header.
+const HEADER_LEN: usize = 8;
+
+/// Size of the receive buffer, enough for an echo reply with its payload.
+const BUF_SIZE: usize = 1024;
+
+/// Send an ICMP echo request to the provided address and return the round
+/// trip time of its reply.  Fails with `TimedOut` if there is no reply within
+/// the timeout.
+pub fn echo(addr: IpAddr, timeout: Duration) -> io::Result<Duration> {
+    let (domain, protocol, request, reply) = match addr {
+        IpAddr::V4(_) => (libc::AF_INET, libc::IPPROTO_ICMP, ICMP_ECHO_REQUEST, ICMP_ECHO_REPLY),
+        IpAddr::V6(_) => (
+            libc::AF_INET6,
+            libc::IPPROTO_ICMPV6,
+            ICMPV6_ECHO_REQUEST,
+            ICMPV6_ECHO_REPLY,
+        ),
+    };
+    let socket = Socket::open(domain, protocol)?;
+
+    // The kernel replaces the identifier with the port of the socket and
+    // fills in the checksum.
+    let seq = (std::process::id() as u16).to_be_bytes();
+    let mut packet = vec![request, 0, 0, 0, 0, 0, seq[0], seq[1]];
+    packet.extend_from_slice(b"rwmstatus");
+
+    let start = Instant::now();
+    socket.send_to(&packet, addr)?;
+    let mut buf = [0u8; BUF_SIZE];
+    loop {
+        let remaining = timeout.saturating_sub(start.elapsed());
+        if remaining.is_zero() {
+            return Err(io::Error::from(io::ErrorKind::TimedOut));
+        }
+
+        // Replies to other pings from the same socket port are skipped.
+        let len = socket.recv(&mut buf, remaining)?;
+        if len >= HEADER_LEN && buf[0] == reply && buf[6..8] == seq {
+            return Ok(start.elapsed());
+        }
+    }
+}
+
+/// An ICMP datagram socket.
+struct Socket(RawFd);
+
+impl Socket {
+    fn open(domain: libc::c_int, protocol: libc::c_int) -> io::Result<Socket> {
+        let fd = unsafe { libc::socket(domain, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, protocol) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Socket(fd))
+    }
+
+    fn send_to(&self, packet: &[u8], addr: IpAddr) -> io::Result<()> {
+        let (storage, len) = sockaddr(SocketAddr::new(addr, 0));
+        let rc = unsafe {
+            libc::sendto(
+                self.0,
+                packet.as_ptr() as *const libc::c_void,
+                packet.len(),
+                0,
+                &storage as *const libc::sockaddr_storage as *const libc::sockaddr,
+                len,
+            )
+        };
+        if rc < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Receive a single packet within the timeout.
+    fn recv(&self, buf: &mut [u8], timeout: Duration) -> io::Result<usize> {
+        let mut pollfd = libc::pollfd {
+            fd: self.0,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // Round up so that a timeout below a millisecond does not spin.
+        let millis = timeout.as_micros().div_ceil(1000) as libc::c_int;
+        let rc = unsafe { libc::poll(&mut pollfd, 1, millis) };
+        if rc < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if rc == 0 {
+            return Err(io::Error::from(io::ErrorKind::TimedOut));
+        }
+
+        let len =
+            unsafe { libc::recv(self.0, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0) };
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(len as usize)
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+/// Convert a socket address to its C representation.
+fn sockaddr(addr: SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let len = match addr {
+        SocketAddr::V4(addr) => {
+            let sin = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in) };
+            sin.sin_family = libc::AF_INET as libc::sa_family_t;
+            sin.sin_port = addr.port().to_be();
+            sin.sin_addr.s_addr = u32::from(*addr.ip()).to_be();
+            mem::size_of::<libc::sockaddr_in>()
+        }
+        SocketAddr::V6(addr) => {
+            let sin6 = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6) };
+            sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            sin6.sin6_port = addr.port().to_be();
+            sin6.sin6_addr.s6_addr = addr.ip().octets();
+            sin6.sin6_scope_id = addr.scope_id();
+            mem::size_of::<libc::sockaddr_in6>()
+        }
+    };
+    (storage, len as libc::socklen_t)
+}
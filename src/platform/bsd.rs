@@ -0,0 +1,471 @@
+//! # BSD
+//!
+//! Reads the temperatures, batteries, and the uptime with `sysctl(3)`.  A
+//! device is named by its sysctl node rather than by a directory.
+//!
+//! FreeBSD and DragonFly read the ACPI thermal zones, e.g.
+//! `hw.acpi.thermal.tz0`, or the CPUs of `coretemp(4)`, e.g. `dev.cpu.0`, and
+//! the ACPI batteries, `hw.acpi.battery`, which ACPI already combines into a
+//! single battery.  OpenBSD reads the temperature and fan sensors of its
+//! sensors framework, e.g. `hw.sensors.cpu0`, and the battery of `apm(4)`,
+//! `/dev/apm`.
+
+// std imports
+#[cfg(not(target_os = "openbsd"))]
+use std::ffi::CString;
+#[cfg(target_os = "openbsd")]
+use std::fs::File;
+use std::io;
+use std::mem;
+#[cfg(target_os = "openbsd")]
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Internal imports
+use format_batt_power;
+use {BatteryReadout, ChargeState, FanReadout, StatusError, TempReadout};
+
+/// `hw.acpi.battery.state` is a combination of these bits.
+#[cfg(not(target_os = "openbsd"))]
+const ACPI_BATT_STAT_DISCHARG: libc::c_int = 0x1;
+#[cfg(not(target_os = "openbsd"))]
+const ACPI_BATT_STAT_CHARGING: libc::c_int = 0x2;
+#[cfg(not(target_os = "openbsd"))]
+const ACPI_BATT_STAT_NOT_PRESENT: libc::c_int = 0x7;
+
+/// 0°C in the tenths of a kelvin of ACPI temperatures, as `sysctl(8)` has it.
+#[cfg(not(target_os = "openbsd"))]
+const ZERO_CELSIUS_DECIKELVIN: libc::c_int = 2731;
+
+/// 0°C in the microkelvin of the sensors framework.
+#[cfg(target_os = "openbsd")]
+const ZERO_CELSIUS_MICROKELVIN: i64 = 273_150_000;
+
+/// The `hw.sensors` node of the sensors framework.
+#[cfg(target_os = "openbsd")]
+const HW_SENSORS: libc::c_int = 11;
+
+/// The types of sensors of `<sys/sensors.h>` which are read.
+#[cfg(target_os = "openbsd")]
+const SENSOR_TEMP: libc::c_int = 0;
+#[cfg(target_os = "openbsd")]
+const SENSOR_FANRPM: libc::c_int = 1;
+
+/// Set in the flags of a sensor whose value cannot be trusted.
+#[cfg(target_os = "openbsd")]
+const SENSOR_FINVALID: libc::c_int = 0x0001;
+
+/// `_IOR('A', 3, struct apm_power_info)` of `<machine/apmvar.h>`.
+#[cfg(target_os = "openbsd")]
+const APM_IOC_GETPOWER: libc::c_ulong = 0x4020_4103;
+
+#[cfg(target_os = "openbsd")]
+const APM_BATT_CHARGING: u8 = 3;
+#[cfg(target_os = "openbsd")]
+const APM_BATTERY_ABSENT: u8 = 4;
+#[cfg(target_os = "openbsd")]
+const APM_AC_OFF: u8 = 0;
+#[cfg(target_os = "openbsd")]
+const APM_AC_ON: u8 = 1;
+
+/// `struct sensordev` of `<sys/sensors.h>` with room for more types of
+/// sensors than there are, the kernel only fills in those it knows.
+#[cfg(target_os = "openbsd")]
+#[repr(C)]
+struct SensorDev {
+    num: libc::c_int,
+    xname: [libc::c_char; 16],
+    maxnumt: [libc::c_int; 64],
+}
+
+/// `struct sensor` of `<sys/sensors.h>`.
+#[cfg(target_os = "openbsd")]
+#[repr(C)]
+struct Sensor {
+    desc: [libc::c_char; 32],
+    tv: libc::timeval,
+    value: i64,
+    kind: libc::c_int,
+    status: libc::c_int,
+    numt: libc::c_int,
+    flags: libc::c_int,
+}
+
+/// `struct apm_power_info` of `<machine/apmvar.h>`.
+#[cfg(target_os = "openbsd")]
+#[repr(C)]
+struct ApmPowerInfo {
+    battery_state: u8,
+    ac_state: u8,
+    battery_life: u8,
+    spare1: u8,
+    minutes_left: libc::c_uint,
+    spare2: [libc::c_uint; 6],
+}
+
+/// Collect the thermal zones or CPUs with a temperature below the provided
+/// node, e.g. `hw.acpi.thermal.tz0` or `dev.cpu.0`.
+#[cfg(not(target_os = "openbsd"))]
+pub fn temp_devices(hwmon_path: &Path) -> Vec<PathBuf> {
+    let device = |index: u32| {
+        ["tz", ""]
+            .iter()
+            .map(|prefix| PathBuf::from(format!("{}.{}{}", hwmon_path.display(), prefix, index)))
+            .find(|device| sysctl_int(&temperature_node(device)).is_ok())
+    };
+    (0..).map_while(device).collect()
+}
+
+/// Collect the sensor devices with temperature sensors, e.g.
+/// `hw.sensors.cpu0`.
+#[cfg(target_os = "openbsd")]
+pub fn temp_devices(hwmon_path: &Path) -> Vec<PathBuf> {
+    sensor_devs()
+        .iter()
+        .filter(|dev| dev.maxnumt[SENSOR_TEMP as usize] > 0)
+        .map(|dev| PathBuf::from(format!("{}.{}", hwmon_path.display(), c_string(&dev.xname))))
+        .collect()
+}
+
+/// Read the temperature of the provided thermal zone or CPU, e.g.
+/// `hw.acpi.thermal.tz0` or `dev.cpu.0`.
+#[cfg(not(target_os = "openbsd"))]
+pub fn get_temp_raw(hwmon: &Path) -> Result<Vec<TempReadout>, StatusError> {
+    let input = temperature_node(hwmon);
+    let decikelvin = sysctl_int(&input)?;
+    Ok(vec![TempReadout {
+        input: PathBuf::from(input),
+        label: None,
+        name: Some(node_name(hwmon)),
+        millidegrees: f64::from(decikelvin - ZERO_CELSIUS_DECIKELVIN) * 100.0,
+    }])
+}
+
+/// Read all the valid temperature sensors of the provided sensor device,
+/// e.g. `hw.sensors.cpu0`, in the order of their index.  Sensors with a
+/// description are labelled with it.
+#[cfg(target_os = "openbsd")]
+pub fn get_temp_raw(hwmon: &Path) -> Result<Vec<TempReadout>, StatusError> {
+    let sensors = read_sensors(hwmon, SENSOR_TEMP, "temp")?;
+    if sensors.is_empty() {
+        return Err(StatusError::NotPresent(
+            format!("temperature sensor in {}", hwmon.display()),
+        ));
+    }
+
+    Ok(sensors
+        .into_iter()
+        .map(|(input, label, microkelvin)| TempReadout {
+            input,
+            label,
+            name: Some(node_name(hwmon)),
+            millidegrees: (microkelvin - ZERO_CELSIUS_MICROKELVIN) as f64 / 1000.0,
+        })
+        .collect())
+}
+
+/// The ACPI thermal zones have no fans.
+#[cfg(not(target_os = "openbsd"))]
+pub fn get_fans_raw(hwmon: &Path) -> Result<Vec<FanReadout>, StatusError> {
+    Err(StatusError::NotPresent(format!("fan in {}", hwmon.display())))
+}
+
+/// Read all the valid fan sensors of the provided sensor device in the order
+/// of their index.
+#[cfg(target_os = "openbsd")]
+pub fn get_fans_raw(hwmon: &Path) -> Result<Vec<FanReadout>, StatusError> {
+    let sensors = read_sensors(hwmon, SENSOR_FANRPM, "fan")?;
+    if sensors.is_empty() {
+        return Err(StatusError::NotPresent(format!("fan in {}", hwmon.display())));
+    }
+
+    Ok(sensors
+        .into_iter()
+        .map(|(input, label, rpm)| FanReadout {
+            input,
+            label,
+            rpm: rpm.max(0) as u64,
+        })
+        .collect())
+}
+
+/// Return the node of the temperature of a thermal zone or CPU.
+#[cfg(not(target_os = "openbsd"))]
+fn temperature_node(device: &Path) -> String {
+    format!("{}.temperature", device.display())
+}
+
+/// Return the provided ACPI battery node if it has any batteries.
+#[cfg(not(target_os = "openbsd"))]
+pub fn batt_devices(batt_path: &Path) -> Vec<PathBuf> {
+    match sysctl_int(&format!("{}.units", batt_path.display())) {
+        Ok(units) if units > 0 => vec![PathBuf::from(batt_path)],
+        _ => vec![],
+    }
+}
+
+/// Return the provided APM device if it has a battery.
+#[cfg(target_os = "openbsd")]
+pub fn batt_devices(batt_path: &Path) -> Vec<PathBuf> {
+    match read_apm(batt_path) {
+        Ok(ref info) if info.battery_state != APM_BATTERY_ABSENT => vec![PathBuf::from(batt_path)],
+        _ => vec![],
+    }
+}
+
+/// Return the charge percentage, status, time remaining, and power of the
+/// provided ACPI battery node.  The time remaining is only known while
+/// discharging.
+#[cfg(not(target_os = "openbsd"))]
+pub fn get_batt_raw(batt: &Path) -> Result<BatteryReadout, StatusError> {
+    let read = |name: &str| sysctl_int(&format!("{}.{}", batt.display(), name));
+
+    let bits = read("state")?;
+    if bits == ACPI_BATT_STAT_NOT_PRESENT {
+        return Err(StatusError::NotPresent(node_name(batt)));
+    }
+    let life = read("life")?;
+    let state = if bits & ACPI_BATT_STAT_CHARGING != 0 {
+        ChargeState::Charging
+    } else if bits & ACPI_BATT_STAT_DISCHARG != 0 {
+        ChargeState::Discharging
+    } else if life >= 100 {
+        ChargeState::Full
+    } else {
+        ChargeState::Unknown
+    };
+
+    // The time is -1 unless the battery is discharging and the rate, in mW,
+    // is only there on recent releases.
+    let time_remaining = read("time")
+        .ok()
+        .filter(|minutes| *minutes >= 0)
+        .map(|minutes| Duration::from_secs(minutes as u64 * 60));
+    let power = match state {
+        ChargeState::Charging | ChargeState::Discharging => read("rate")
+            .ok()
+            .filter(|rate| *rate > 0)
+            .map(|rate| f64::from(rate) / 1000.0),
+        _ => None,
+    };
+
+    Ok(BatteryReadout {
+        percent: f64::from(life),
+        state,
+        time_remaining,
+        power,
+        energy: None,
+        health: None,
+        cycles: None,
+    })
+}
+
+/// Return the charge percentage, status, and time remaining of the battery
+/// of the provided APM device.  The time remaining is only known while
+/// discharging.
+#[cfg(target_os = "openbsd")]
+pub fn get_batt_raw(batt: &Path) -> Result<BatteryReadout, StatusError> {
+    let info = read_apm(batt)?;
+    if info.battery_state == APM_BATTERY_ABSENT {
+        return Err(StatusError::NotPresent(batt.display().to_string()));
+    }
+    if info.battery_life > 100 {
+        return Err(StatusError::NotPresent(format!("{} charge", batt.display())));
+    }
+
+    let state = if info.battery_state == APM_BATT_CHARGING {
+        ChargeState::Charging
+    } else if info.ac_state == APM_AC_OFF {
+        ChargeState::Discharging
+    } else if info.ac_state == APM_AC_ON && info.battery_life == 100 {
+        ChargeState::Full
+    } else {
+        ChargeState::Unknown
+    };
+
+    // An unknown time is all ones.
+    let time_remaining = Some(info.minutes_left)
+        .filter(|minutes| state == ChargeState::Discharging && *minutes != libc::c_uint::MAX)
+        .map(|minutes| Duration::from_secs(u64::from(minutes) * 60));
+
+    Ok(BatteryReadout {
+        percent: f64::from(info.battery_life),
+        state,
+        time_remaining,
+        power: None,
+        energy: None,
+        health: None,
+        cycles: None,
+    })
+}
+
+/// Return the readout of the first present battery, which already combines
+/// all the batteries.
+pub fn get_total_batt_raw(batts: &[PathBuf]) -> Result<BatteryReadout, StatusError> {
+    batts
+        .iter()
+        .find_map(|batt| get_batt_raw(batt).ok())
+        .ok_or_else(|| StatusError::NotPresent(String::from("battery")))
+}
+
+/// Read the estimated time until the provided battery is empty while
+/// discharging.
+pub fn read_batt_time(batt: &Path) -> Result<Duration, StatusError> {
+    get_batt_raw(batt)?
+        .time_remaining
+        .ok_or_else(|| StatusError::NotPresent(format!("{} discharging", batt.display())))
+}
+
+/// The remaining energy is not reported.
+pub fn get_batt_energy(batt: &Path) -> Result<String, StatusError> {
+    Err(StatusError::NotPresent(format!("{} energy", batt.display())))
+}
+
+/// Return the power flowing in or out of the provided battery, negative
+/// while discharging and positive while charging.
+pub fn get_batt_power(batt: &Path) -> Result<String, StatusError> {
+    let readout = get_batt_raw(batt)?;
+    let watts = readout
+        .power
+        .ok_or_else(|| StatusError::NotPresent(format!("{} power", batt.display())))?;
+    Ok(format_batt_power(watts, readout.state))
+}
+
+/// Nothing is cached, every readout is a single `sysctl` call.
+pub fn forget_static_attributes() {}
+
+/// Return the time since boot, as of `kern.boottime`.
+pub fn get_uptime_raw() -> Result<Duration, StatusError> {
+    let mut boottime: libc::timeval = unsafe { mem::zeroed() };
+    sysctl(&[libc::CTL_KERN, libc::KERN_BOOTTIME], &mut boottime)
+        .map_err(|err| sysctl_error("kern.boottime", err))?;
+
+    let boot = UNIX_EPOCH + Duration::new(boottime.tv_sec as u64, boottime.tv_usec as u32 * 1000);
+    Ok(SystemTime::now().duration_since(boot).unwrap_or_default())
+}
+
+/// Return the last component of a node, e.g. `tz0` for
+/// `hw.acpi.thermal.tz0`.
+fn node_name(node: &Path) -> String {
+    let node = node.to_string_lossy();
+    String::from(node.rsplit('.').next().unwrap_or_default())
+}
+
+/// Read the node with the provided MIB into `value`, which must have the
+/// type of the node.
+fn sysctl<T>(mib: &[libc::c_int], value: &mut T) -> io::Result<()> {
+    let mut len = mem::size_of::<T>();
+    let rc = unsafe {
+        libc::sysctl(
+            mib.as_ptr(),
+            mib.len() as libc::c_uint,
+            value as *mut T as *mut libc::c_void,
+            &mut len,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if rc < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Read the integer node with the provided name, e.g. `hw.acpi.battery.life`.
+#[cfg(not(target_os = "openbsd"))]
+fn sysctl_int(name: &str) -> Result<libc::c_int, StatusError> {
+    let c_name = CString::new(name).map_err(|_| StatusError::InvalidArg(name.into()))?;
+    let mut value: libc::c_int = 0;
+    let mut len = mem::size_of::<libc::c_int>();
+    let rc = unsafe {
+        libc::sysctlbyname(
+            c_name.as_ptr(),
+            &mut value as *mut libc::c_int as *mut libc::c_void,
+            &mut len,
+            ptr::null(),
+            0,
+        )
+    };
+    if rc < 0 {
+        return Err(sysctl_error(name, io::Error::last_os_error()));
+    }
+    Ok(value)
+}
+
+/// Name the node which could not be read in the error, like the file of a
+/// sysfs attribute.
+fn sysctl_error(name: &str, err: io::Error) -> StatusError {
+    StatusError::File(PathBuf::from(name), Box::new(StatusError::Io(err)))
+}
+
+/// Collect all the attached sensor devices.
+#[cfg(target_os = "openbsd")]
+fn sensor_devs() -> Vec<SensorDev> {
+    let mut devs = vec![];
+    for num in 0.. {
+        let mut dev: SensorDev = unsafe { mem::zeroed() };
+        match sysctl(&[libc::CTL_HW, HW_SENSORS, num], &mut dev) {
+            Ok(()) => devs.push(dev),
+            // Detached devices leave gaps in the numbering.
+            Err(ref err) if err.raw_os_error() == Some(libc::ENXIO) => continue,
+            Err(_) => break,
+        }
+    }
+    devs
+}
+
+/// Read the valid sensors of the provided type of the provided sensor device,
+/// each with its node, e.g. `hw.sensors.cpu0.temp0`, its description if it
+/// has one, and its value.
+#[cfg(target_os = "openbsd")]
+fn read_sensors(
+    device: &Path,
+    kind: libc::c_int,
+    prefix: &str,
+) -> Result<Vec<(PathBuf, Option<String>, i64)>, StatusError> {
+    let xname = node_name(device);
+    let dev = sensor_devs()
+        .into_iter()
+        .find(|dev| c_string(&dev.xname) == xname)
+        .ok_or_else(|| StatusError::NotPresent(device.display().to_string()))?;
+
+    let mut sensors = vec![];
+    for numt in 0..dev.maxnumt[kind as usize] {
+        let input = format!("{}.{}{}", device.display(), prefix, numt);
+        let mut sensor: Sensor = unsafe { mem::zeroed() };
+        sysctl(&[libc::CTL_HW, HW_SENSORS, dev.num, kind, numt], &mut sensor)
+            .map_err(|err| sysctl_error(&input, err))?;
+        if sensor.flags & SENSOR_FINVALID != 0 {
+            continue;
+        }
+
+        let desc = c_string(&sensor.desc);
+        let label = if desc.is_empty() { None } else { Some(desc) };
+        sensors.push((PathBuf::from(input), label, sensor.value));
+    }
+    Ok(sensors)
+}
+
+/// Read the battery of the provided APM device.
+#[cfg(target_os = "openbsd")]
+fn read_apm(apm: &Path) -> Result<ApmPowerInfo, StatusError> {
+    let error = |err| StatusError::File(PathBuf::from(apm), Box::new(StatusError::Io(err)));
+    let file = File::open(apm).map_err(error)?;
+    let mut info: ApmPowerInfo = unsafe { mem::zeroed() };
+    let rc = unsafe {
+        libc::ioctl(file.as_raw_fd(), APM_IOC_GETPOWER as _, &mut info as *mut ApmPowerInfo)
+    };
+    if rc < 0 {
+        return Err(error(io::Error::last_os_error()));
+    }
+    Ok(info)
+}
+
+/// Convert a NUL-terminated C string of a fixed-size array.
+#[cfg(target_os = "openbsd")]
+fn c_string(chars: &[libc::c_char]) -> String {
+    let bytes: Vec<u8> = chars.iter().take_while(|c| **c != 0).map(|c| *c as u8).collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
@@ -0,0 +1,503 @@
+//! # Linux
+//!
+//! Reads the temperatures and fans of the hwmon devices and the batteries of
+//! the power supply class from sysfs and the uptime from procfs.
+//!
+//! The attributes which do not change while a device is plugged in, such as
+//! the design capacity of a battery or the labels of a hwmon device's sensors,
+//! are only read once, until `forget_static_attributes`.  Whether a battery
+//! is present is read every time as swapping it may only change it.
+
+// std imports
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Internal imports
+use {device_name, format_batt_power, read_file, read_number, rooted};
+use {BatteryReadout, ChargeState, FanReadout, RwmStatus, StatusError, TempReadout};
+
+/// The static attributes read so far, see `read_static`.
+static STATIC_ATTRIBUTES: Mutex<Option<StaticAttributes>> = Mutex::new(None);
+
+/// The static attributes of the devices.
+#[derive(Default)]
+struct StaticAttributes {
+    /// The contents of each attribute file, `None` if it does not exist.
+    files: HashMap<PathBuf, Option<String>>,
+    /// The indices of each kind of sensor input of each hwmon device.
+    sensors: HashMap<(PathBuf, &'static str), Vec<u32>>,
+}
+
+/// Forget the static attributes read so far, e.g. because a device has been
+/// added or removed, so that they are read again when they are next needed.
+pub fn forget_static_attributes() {
+    *STATIC_ATTRIBUTES.lock().unwrap() = None;
+}
+
+/// Read an attribute which does not change while its device is plugged in,
+/// e.g. a label, only the first time.  A missing attribute is remembered as
+/// such, e.g. the charge of a battery which reports its energy.  Errors name
+/// the file.
+fn read_static(path: &Path) -> Result<String, StatusError> {
+    let not_found = || {
+        let err = io::Error::from_raw_os_error(libc::ENOENT);
+        StatusError::File(PathBuf::from(path), Box::new(StatusError::Io(err)))
+    };
+
+    let mut cache = STATIC_ATTRIBUTES.lock().unwrap();
+    let files = &mut cache.get_or_insert_with(StaticAttributes::default).files;
+    if let Some(contents) = files.get(path) {
+        return contents.clone().ok_or_else(not_found);
+    }
+    match read_to_string(path) {
+        Ok(contents) => {
+            files.insert(PathBuf::from(path), Some(contents.clone()));
+            Ok(contents)
+        }
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+            files.insert(PathBuf::from(path), None);
+            Err(not_found())
+        }
+        Err(err) => Err(StatusError::File(PathBuf::from(path), Box::new(StatusError::Io(err)))),
+    }
+}
+
+/// Collect the hwmon devices in the provided directory, e.g. `hwmon0`.
+pub fn temp_devices(hwmon_path: &Path) -> Vec<PathBuf> {
+    RwmStatus::get_paths(hwmon_path, "hwmon")
+}
+
+/// Collect the batteries in the provided directory, e.g. `BAT0`.
+pub fn batt_devices(batt_path: &Path) -> Vec<PathBuf> {
+    RwmStatus::get_paths(batt_path, "BAT")
+}
+
+/// Read all the temp*_input sensors of the provided monitor in the order of
+/// their index.  Sensors which cannot be read, e.g. because a driver reports
+/// `ENODATA` while the sensor is off, are left out, it fails only if none of
+/// them can be read.
+pub fn get_temp_raw(hwmon: &Path) -> Result<Vec<TempReadout>, StatusError> {
+    let indices = sensor_indices(hwmon, "temp")?;
+    if indices.is_empty() {
+        return Err(StatusError::NotPresent(
+            format!("temperature sensor in {}", hwmon.display()),
+        ));
+    }
+
+    let name = read_static(&hwmon.join("name"))
+        .ok()
+        .map(|name| String::from(name.trim()));
+    let mut temps = vec![];
+    let mut error = None;
+    for index in indices {
+        let input = hwmon.join(format!("temp{}_input", index));
+        let millidegrees = match read_number(&input) {
+            Ok(millidegrees) => millidegrees,
+            Err(err) => {
+                error.get_or_insert(err);
+                continue;
+            }
+        };
+        let label = read_static(&hwmon.join(format!("temp{}_label", index)))
+            .ok()
+            .map(|label| String::from(label.trim()));
+        temps.push(TempReadout {
+            input,
+            label,
+            name: name.clone(),
+            millidegrees,
+        });
+    }
+
+    match error {
+        Some(err) if temps.is_empty() => Err(err),
+        _ => Ok(temps),
+    }
+}
+
+/// Return the indices of all the sensor inputs of the provided kind, e.g.
+/// the temp*_input files for `temp`, of the provided monitor in ascending
+/// order.  They are only looked up the first time.
+fn sensor_indices(hwmon: &Path, kind: &'static str) -> Result<Vec<u32>, StatusError> {
+    let key = (PathBuf::from(hwmon), kind);
+    let cached = STATIC_ATTRIBUTES
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|cache| cache.sensors.get(&key).cloned());
+    if let Some(indices) = cached {
+        return Ok(indices);
+    }
+
+    let mut indices: Vec<u32> = hwmon
+        .read_dir()
+        .map_err(|err| StatusError::File(PathBuf::from(hwmon), Box::new(StatusError::Io(err))))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            entry.file_name()
+                .to_str()?
+                .strip_prefix(kind)?
+                .strip_suffix("_input")?
+                .parse()
+                .ok()
+        })
+        .collect();
+
+    indices.sort_unstable();
+    STATIC_ATTRIBUTES
+        .lock()
+        .unwrap()
+        .get_or_insert_with(StaticAttributes::default)
+        .sensors
+        .insert(key, indices.clone());
+    Ok(indices)
+}
+
+/// Read all the fan*_input sensors of the provided monitor in the order of
+/// their index.
+pub fn get_fans_raw(hwmon: &Path) -> Result<Vec<FanReadout>, StatusError> {
+    let indices = sensor_indices(hwmon, "fan")?;
+    if indices.is_empty() {
+        return Err(StatusError::NotPresent(format!("fan in {}", hwmon.display())));
+    }
+
+    indices
+        .iter()
+        .map(|index| {
+            let input = hwmon.join(format!("fan{}_input", index));
+            let label = read_static(&hwmon.join(format!("fan{}_label", index)))
+                .ok()
+                .map(|label| String::from(label.trim()));
+            let rpm = read_number(&input)?;
+            Ok(FanReadout { input, label, rpm })
+        })
+        .collect()
+}
+
+/// Return the time since boot from `/proc/uptime` or, if it cannot be read,
+/// from `sysinfo(2)`.
+pub fn get_uptime_raw() -> Result<Duration, StatusError> {
+    let secs = match read_to_string(rooted("/proc/uptime")) {
+        Ok(contents) => parse_uptime(&contents)?,
+        Err(_) => get_sysinfo_uptime()?,
+    };
+    Ok(Duration::from_secs(secs))
+}
+
+/// Return the whole seconds since boot reported by `sysinfo(2)`.
+fn get_sysinfo_uptime() -> Result<u64, StatusError> {
+    let mut info: libc::sysinfo = unsafe { mem::zeroed() };
+
+    // sysinfo returns 0 on success and -1 on error.
+    let rc = unsafe { libc::sysinfo(&mut info) };
+    if rc != 0 {
+        return Err(StatusError::System(rc));
+    }
+    Ok(info.uptime as u64)
+}
+
+/// Parse the whole seconds since boot from the contents of `/proc/uptime`.
+fn parse_uptime(contents: &str) -> Result<u64, StatusError> {
+    let secs: f64 = contents.split_whitespace().next().unwrap_or("").parse()?;
+    // The fractional seconds are truncated.
+    Ok(secs as u64)
+}
+
+/// Return the charge percentage, status, time remaining, power, and energy of
+/// the battery at the provided path.
+pub fn get_batt_raw(batt: &Path) -> Result<BatteryReadout, StatusError> {
+    let (remaining_capacity, design_capacity) = match read_batt_capacity(batt)? {
+        BattCapacity::Charge(now, design) | BattCapacity::Energy(now, design) => (now, design),
+    };
+
+    let state = get_batt_status(batt);
+    // A full or idle battery may still report a small residual power.
+    let power = match state {
+        ChargeState::Charging | ChargeState::Discharging => read_batt_power(batt)
+            .ok()
+            .map(|power| power / 1_000_000.0)
+            .filter(|power| *power > 0.0 && power.is_finite()),
+        _ => None,
+    };
+
+    Ok(BatteryReadout {
+        percent: (remaining_capacity / design_capacity) * 100.0,
+        state,
+        time_remaining: read_batt_time(batt).ok(),
+        power,
+        energy: read_batt_energy(batt).ok().map(|energy| energy / 1_000_000.0),
+        health: read_batt_health(batt).ok(),
+        // Drivers which do not count the cycles report 0.
+        cycles: read_batt_value(batt, "cycle_count")
+            .ok()
+            .map(|cycles| cycles as u64)
+            .filter(|cycles| *cycles > 0),
+    })
+}
+
+/// Read the full charge capacity of the battery at the provided path as a
+/// percentage of its design capacity.
+fn read_batt_health(batt: &Path) -> Result<f64, StatusError> {
+    let (full, design) = match read_static_batt_value(batt, "charge_full_design") {
+        Ok(design) => (read_batt_value(batt, "charge_full")?, design),
+        Err(_) => {
+            let design = read_static_batt_value(batt, "energy_full_design")?;
+            (read_batt_value(batt, "energy_full")?, design)
+        }
+    };
+
+    if design == 0.0 {
+        return Err(StatusError::NotPresent(format!("{} design capacity", device_name(batt))));
+    }
+    Ok(full / design * 100.0)
+}
+
+/// Return the combined readout of all the present batteries, see
+/// `get_total_batt`.  The time remaining is estimated from the combined
+/// capacity and rate of the (dis)charging batteries and the power, energy,
+/// and health are those of all the batteries which report them.
+pub fn get_total_batt_raw(batts: &[PathBuf]) -> Result<BatteryReadout, StatusError> {
+    let capacities: Vec<(&PathBuf, BattCapacity)> = batts
+        .iter()
+        .filter_map(|batt| read_batt_capacity(batt).ok().map(|capacity| (batt, capacity)))
+        .collect();
+
+    if capacities.is_empty() {
+        return Err(StatusError::NotPresent(String::from("battery")));
+    }
+
+    let mixed = capacities.iter().any(|(_, c)| matches!(c, BattCapacity::Charge(..)))
+        && capacities.iter().any(|(_, c)| matches!(c, BattCapacity::Energy(..)));
+
+    let mut remaining_capacity = 0.0;
+    let mut design_capacity = 0.0;
+    // The full capacity and the rate are in the same unit as the remaining
+    // capacity, i.e. µAh and µA unless the charge is converted to energy.
+    let mut full_capacity = Some(0.0);
+    let mut rates = vec![];
+    let mut energy = Some(0.0);
+    let mut statuses = vec![];
+    for (batt, capacity) in capacities {
+        let (now, design, scale, is_energy) = match capacity {
+            BattCapacity::Charge(now, design) if mixed => {
+                match read_batt_value(batt, "voltage_now") {
+                    Ok(voltage) => (now, design, voltage / 1_000_000.0, true),
+                    Err(_) => continue,
+                }
+            }
+            BattCapacity::Charge(now, design) => (now, design, 1.0, false),
+            BattCapacity::Energy(now, design) => (now, design, 1.0, true),
+        };
+        remaining_capacity += now * scale;
+        design_capacity += design * scale;
+
+        let full_name = if matches!(capacity, BattCapacity::Charge(..)) {
+            "charge_full"
+        } else {
+            "energy_full"
+        };
+        full_capacity = match (full_capacity, read_batt_value(batt, full_name)) {
+            (Some(total), Ok(full)) => Some(total + full * scale),
+            _ => None,
+        };
+        energy = match (energy, read_batt_energy(batt)) {
+            (Some(total), Ok(batt_energy)) => Some(total + batt_energy),
+            _ => None,
+        };
+
+        let status = get_batt_status(batt);
+        if matches!(status, ChargeState::Charging | ChargeState::Discharging) {
+            let power = read_batt_power(batt).unwrap_or_default();
+            let rate = if is_energy {
+                power
+            } else {
+                read_batt_current(batt).unwrap_or_default()
+            };
+            rates.push((status, rate, power));
+        }
+        statuses.push(status);
+    }
+
+    let status = [ChargeState::Charging, ChargeState::Discharging, ChargeState::Unknown]
+        .iter()
+        .find(|status| statuses.contains(status))
+        .cloned()
+        .unwrap_or(ChargeState::Full);
+
+    // Only the batteries which are (dis)charging like the combined battery
+    // count, e.g. not one which is charged while the other is discharging.
+    let (rate, power) = rates
+        .into_iter()
+        .filter(|(batt_status, _, _)| *batt_status == status)
+        .fold((0.0, 0.0), |(rate, power), (_, batt_rate, batt_power)| {
+            (rate + batt_rate, power + batt_power)
+        });
+    let hours = match (status, full_capacity) {
+        _ if rate == 0.0 || !rate.is_finite() => None,
+        (ChargeState::Discharging, _) => Some(remaining_capacity / rate),
+        (ChargeState::Charging, Some(full)) => Some((full - remaining_capacity).max(0.0) / rate),
+        _ => None,
+    };
+    let power = power / 1_000_000.0;
+
+    Ok(BatteryReadout {
+        percent: (remaining_capacity / design_capacity) * 100.0,
+        state: status,
+        time_remaining: hours.map(|hours| Duration::from_secs((hours * 60.0).round() as u64 * 60)),
+        power: Some(power).filter(|power| *power > 0.0 && power.is_finite()),
+        energy: energy.map(|energy| energy / 1_000_000.0),
+        health: full_capacity
+            .filter(|_| design_capacity > 0.0)
+            .map(|full| full / design_capacity * 100.0),
+        cycles: None,
+    })
+}
+
+/// Remaining and design capacity of a battery.
+enum BattCapacity {
+    /// Charge in µAh.
+    Charge(f64, f64),
+    /// Energy in µWh.
+    Energy(f64, f64),
+}
+
+/// Read the remaining and design capacity of the battery at the provided
+/// path.
+fn read_batt_capacity(batt: &Path) -> Result<BattCapacity, StatusError> {
+    if !read_file(batt.join("present"))?.starts_with('1') {
+        return Err(StatusError::NotPresent(device_name(batt)));
+    }
+
+    match read_static_batt_value(batt, "charge_full_design") {
+        Ok(design) => Ok(BattCapacity::Charge(read_batt_value(batt, "charge_now")?, design)),
+        Err(_) => {
+            let design = read_static_batt_value(batt, "energy_full_design")?;
+            Ok(BattCapacity::Energy(read_batt_value(batt, "energy_now")?, design))
+        }
+    }
+}
+
+/// Read the estimated time until the battery at the provided path is empty
+/// while discharging or full while charging, rounded to the minute.
+pub fn read_batt_time(batt: &Path) -> Result<Duration, StatusError> {
+    // Batteries report either energy (µWh) and power (µW) or charge (µAh) and
+    // current (µA).  The rate is converted with voltage_now (µV) if it is
+    // reported in the other convention.
+    let (now, full, rate) = match read_batt_value(batt, "energy_now") {
+        Ok(energy_now) => {
+            let energy_full = read_batt_value(batt, "energy_full")?;
+            (energy_now, energy_full, read_batt_power(batt)?)
+        }
+        Err(_) => {
+            let charge_now = read_batt_value(batt, "charge_now")?;
+            let charge_full = read_batt_value(batt, "charge_full")?;
+            (charge_now, charge_full, read_batt_current(batt)?)
+        }
+    };
+
+    if rate == 0.0 || !rate.is_finite() {
+        return Err(StatusError::NotPresent(format!("{} discharge rate", device_name(batt))));
+    }
+
+    let hours = match get_batt_status(batt) {
+        ChargeState::Discharging => now / rate,
+        ChargeState::Charging => (full - now).max(0.0) / rate,
+        _ => return Err(StatusError::NotPresent(format!("{} (dis)charging", device_name(batt)))),
+    };
+
+    let minutes = (hours * 60.0).round() as u64;
+    Ok(Duration::from_secs(minutes * 60))
+}
+
+/// Read the battery power in µW from power_now or compute it from the current
+/// and voltage.
+fn read_batt_power(batt: &Path) -> Result<f64, StatusError> {
+    read_batt_value(batt, "power_now").or_else(|_| {
+        let current = read_batt_value(batt, "current_now")?;
+        let voltage = read_batt_value(batt, "voltage_now")?;
+        Ok(current * voltage / 1_000_000.0)
+    })
+}
+
+/// Read the battery current in µA from current_now or compute it from the
+/// power and voltage.
+fn read_batt_current(batt: &Path) -> Result<f64, StatusError> {
+    read_batt_value(batt, "current_now").or_else(|_| {
+        let power = read_batt_value(batt, "power_now")?;
+        let voltage = read_batt_value(batt, "voltage_now")?;
+        Ok(power * 1_000_000.0 / voltage)
+    })
+}
+
+/// Read a numeric battery attribute.  The sign is dropped as drivers do not
+/// agree on the sign convention of the current and power.
+fn read_batt_value(batt: &Path, name: &str) -> Result<f64, StatusError> {
+    let value: i64 = read_number(batt.join(name))?;
+    Ok(value.abs() as f64)
+}
+
+/// Read a numeric battery attribute which does not change, e.g. the design
+/// capacity, see `read_static`.
+fn read_static_batt_value(batt: &Path, name: &str) -> Result<f64, StatusError> {
+    let path = batt.join(name);
+    let value: i64 = read_static(&path)?
+        .trim()
+        .parse()
+        .map_err(|err| StatusError::File(path, Box::new(StatusError::from(err))))?;
+    Ok(value.abs() as f64)
+}
+
+/// Return the remaining energy of the battery at the provided path in
+/// watt-hours.
+pub fn get_batt_energy(batt: &Path) -> Result<String, StatusError> {
+    if !read_file(batt.join("present"))?.starts_with('1') {
+        return Err(StatusError::NotPresent(device_name(batt)));
+    }
+
+    let energy = read_batt_energy(batt)?;
+    let status = get_batt_status(batt);
+    Ok(format!("{:.1}Wh{}", energy / 1_000_000.0, status.symbol()))
+}
+
+/// Read the remaining energy of the battery at the provided path in µWh.
+fn read_batt_energy(batt: &Path) -> Result<f64, StatusError> {
+    // energy_now is in µWh whereas charge_now is in µAh and needs to be
+    // multiplied by voltage_now in µV.
+    match read_to_string(batt.join("energy_now")) {
+        Ok(contents) => Ok(contents.trim().parse::<u64>()? as f64),
+        Err(_) => {
+            let charge: u64 = read_number(batt.join("charge_now"))?;
+            let voltage: u64 = read_number(batt.join("voltage_now"))?;
+            Ok((charge as f64) * (voltage as f64) / 1_000_000.0)
+        }
+    }
+}
+
+/// Return the power flowing in or out of the battery at the provided path.
+/// The value is negative while discharging and positive while charging.  The
+/// sign is derived from the battery status as drivers do not agree on the
+/// sign convention of `current_now` and `power_now`.
+pub fn get_batt_power(batt: &Path) -> Result<String, StatusError> {
+    let watts = read_batt_power(batt)? / 1_000_000.0;
+    Ok(format_batt_power(watts, get_batt_status(batt)))
+}
+
+/// Return the status of the battery at the provided path.
+fn get_batt_status(batt: &Path) -> ChargeState {
+    match read_to_string(batt.join("status")) {
+        Ok(contents) => {
+            match contents.trim() {
+                "Full" => ChargeState::Full,
+                "Discharging" => ChargeState::Discharging,
+                "Charging" => ChargeState::Charging,
+                _ => ChargeState::Unknown,
+            }
+        }
+        Err(_) => ChargeState::Unknown,
+    }
+}
@@ -0,0 +1,16 @@
+//! # Platform
+//!
+//! The readouts which each kernel exposes its own way: temperatures, fans,
+//! batteries, and the uptime.  Linux reads them from sysfs and procfs and the
+//! BSDs from `sysctl(3)`.  Each backend provides the same functions, which
+//! the crate root re-exports, together with the discovery of the devices.
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+pub use self::linux::*;
+
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly", target_os = "openbsd"))]
+mod bsd;
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly", target_os = "openbsd"))]
+pub use self::bsd::*;
@@ -0,0 +1,116 @@
+//! # Signal handling
+//!
+//! SIGTERM and SIGINT only set a flag which the main loop checks so that it
+//! can clean up before exiting.  SIGHUP requests a reload of the
+//! configuration.  SIGUSR1 and SIGRTMIN+n request an immediate
+//! refresh of all the sections or of the n-th section of the layout, e.g.
+//! from a keybinding which changes the brightness.  SIGRTMIN+n is only
+//! handled on Linux and FreeBSD.
+
+// std imports
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Set once SIGTERM or SIGINT has been received.
+static TERMINATE: AtomicBool = AtomicBool::new(false);
+
+/// Set when SIGHUP has been received until the reload is taken.
+static RELOAD: AtomicBool = AtomicBool::new(false);
+
+/// The requested refreshes, bit 0 for SIGUSR1 and bit n for SIGRTMIN+n.
+static REFRESH: AtomicU64 = AtomicU64::new(0);
+
+/// The value of SIGRTMIN, which is only known at runtime.
+static SIGRTMIN: AtomicI32 = AtomicI32::new(0);
+
+/// How often an interruptible wait checks for termination.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+extern "C" fn handle_terminate(_: libc::c_int) {
+    TERMINATE.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn handle_reload(_: libc::c_int) {
+    RELOAD.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn handle_refresh(signum: libc::c_int) {
+    let bit = if signum == libc::SIGUSR1 {
+        0
+    } else {
+        signum - SIGRTMIN.load(Ordering::SeqCst)
+    };
+    if (0..64).contains(&bit) {
+        REFRESH.fetch_or(1 << bit, Ordering::SeqCst);
+    }
+}
+
+/// Return SIGRTMIN and SIGRTMAX if there are real-time signals.
+#[cfg(target_os = "linux")]
+fn realtime_signals() -> Option<(libc::c_int, libc::c_int)> {
+    Some((libc::SIGRTMIN(), libc::SIGRTMAX()))
+}
+
+/// Return SIGRTMIN and SIGRTMAX of `<sys/signal.h>`.
+#[cfg(target_os = "freebsd")]
+fn realtime_signals() -> Option<(libc::c_int, libc::c_int)> {
+    Some((65, 126))
+}
+
+/// Real-time signals are not used elsewhere.
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+fn realtime_signals() -> Option<(libc::c_int, libc::c_int)> {
+    None
+}
+
+/// Install the SIGTERM, SIGINT, SIGHUP, SIGUSR1, and SIGRTMIN+n handlers.
+pub fn install() {
+    let realtime = realtime_signals();
+    if let Some((min, _)) = realtime {
+        SIGRTMIN.store(min, Ordering::SeqCst);
+    }
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handle_terminate as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGTERM, &action, std::ptr::null_mut());
+        libc::sigaction(libc::SIGINT, &action, std::ptr::null_mut());
+
+        action.sa_sigaction = handle_reload as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::sigaction(libc::SIGHUP, &action, std::ptr::null_mut());
+
+        action.sa_sigaction = handle_refresh as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::sigaction(libc::SIGUSR1, &action, std::ptr::null_mut());
+        if let Some((min, max)) = realtime {
+            for signum in min + 1..=max {
+                libc::sigaction(signum, &action, std::ptr::null_mut());
+            }
+        }
+    }
+}
+
+/// Return true if SIGTERM or SIGINT has been received.
+pub fn terminated() -> bool {
+    TERMINATE.load(Ordering::SeqCst)
+}
+
+/// Return true if a signal which the main loop should act on straight away
+/// has been received.
+pub fn pending() -> bool {
+    terminated() || RELOAD.load(Ordering::SeqCst) || REFRESH.load(Ordering::SeqCst) != 0
+}
+
+/// Return true if SIGHUP has been received since the last call.
+pub fn take_reload() -> bool {
+    RELOAD.swap(false, Ordering::SeqCst)
+}
+
+/// Return and clear the requested refreshes, `None` to refresh all the
+/// sections and `Some(n)` to refresh the n-th section counting from 1.
+pub fn take_refresh() -> Vec<Option<usize>> {
+    let refresh = REFRESH.swap(0, Ordering::SeqCst);
+    (0..64)
+        .filter(|bit| refresh & (1 << bit) != 0)
+        .map(|bit| if bit == 0 { None } else { Some(bit) })
+        .collect()
+}
@@ -0,0 +1,140 @@
+//! # Output sinks
+//!
+//! Every status update is written to each of the configured sinks.
+
+// std imports
+#[cfg(feature = "x11")]
+use std::ffi::CString;
+use std::io::{self, Write};
+
+// x11 imports
+#[cfg(feature = "x11")]
+use x11::xlib::{Display, XCloseDisplay, XDefaultRootWindow, XOpenDisplay, XStoreName, XSync};
+
+// Lib imports
+use rwmstatus::{Block, StatusFormat, StatusSnapshot};
+
+/// A destination for status updates.
+pub trait OutputSink {
+    /// Return a description of the sink for error messages.
+    fn name(&self) -> String;
+
+    /// Write a status update.  `status` is the rendered status line of the
+    /// snapshot.
+    fn write(&mut self, snapshot: &StatusSnapshot, status: &str) -> io::Result<()>;
+
+    /// Use the provided layout from now on, e.g. after the configuration
+    /// has been reloaded.
+    fn set_format(&mut self, _format: &StatusFormat) {}
+
+    /// Clean up before exiting.  The last status is left behind if `keep` is
+    /// true.
+    fn close(&mut self, _keep: bool) {}
+}
+
+/// Prints a line per update to stdout.
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn name(&self) -> String {
+        String::from("stdout")
+    }
+
+    fn write(&mut self, _: &StatusSnapshot, status: &str) -> io::Result<()> {
+        writeln!(io::stdout(), "{}", status)
+    }
+}
+
+/// Prints the updates to stdout in the i3bar JSON protocol: a header followed
+/// by an infinite array with an array of blocks per update.
+pub struct I3barSink {
+    format: StatusFormat,
+    started: bool,
+}
+
+impl I3barSink {
+    /// Create a sink with a block for each section of the provided layout.
+    pub fn new(format: StatusFormat) -> I3barSink {
+        I3barSink {
+            format,
+            started: false,
+        }
+    }
+}
+
+impl OutputSink for I3barSink {
+    fn name(&self) -> String {
+        String::from("i3bar")
+    }
+
+    fn write(&mut self, snapshot: &StatusSnapshot, _: &str) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        if !self.started {
+            writeln!(stdout, "{{\"version\":1}}")?;
+            writeln!(stdout, "[")?;
+        }
+
+        let blocks: Vec<String> = snapshot
+            .blocks(&self.format)
+            .iter()
+            .map(Block::to_json)
+            .collect();
+        writeln!(stdout, "{}[{}]", if self.started { "," } else { "" }, blocks.join(","))?;
+        self.started = true;
+        Ok(())
+    }
+
+    fn set_format(&mut self, format: &StatusFormat) {
+        self.format = format.clone();
+    }
+}
+
+/// Sets the status as the name of the X11 root window.
+#[cfg(feature = "x11")]
+pub struct X11Sink {
+    display: *mut Display,
+}
+
+#[cfg(feature = "x11")]
+impl X11Sink {
+    /// Open the default display.
+    pub fn open() -> Result<X11Sink, String> {
+        let display = unsafe { XOpenDisplay(std::ptr::null()) };
+        if display.is_null() {
+            return Err(String::from("cannot open display."));
+        }
+        Ok(X11Sink { display })
+    }
+
+    /// Set the name of the root window.
+    fn store_name(&self, status: &str) -> io::Result<()> {
+        let status = CString::new(status)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        unsafe {
+            XStoreName(self.display, XDefaultRootWindow(self.display), status.as_ptr());
+            XSync(self.display, false as i32);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "x11")]
+impl OutputSink for X11Sink {
+    fn name(&self) -> String {
+        String::from("X11")
+    }
+
+    fn write(&mut self, _: &StatusSnapshot, status: &str) -> io::Result<()> {
+        self.store_name(status)
+    }
+
+    fn close(&mut self, keep: bool) {
+        // Do not leave a stale status behind.
+        if !keep {
+            let _ = self.store_name("");
+        }
+        unsafe {
+            XCloseDisplay(self.display);
+        }
+    }
+}
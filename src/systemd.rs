@@ -0,0 +1,75 @@
+//! # systemd integration
+//!
+//! A minimal implementation of the `sd_notify` protocol used to report the
+//! service status to systemd and to keep its watchdog from restarting the
+//! service, which it does once the main loop stops pinging it, e.g. because
+//! a readout hangs.
+
+// std imports
+use std::env;
+use std::io;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::process;
+use std::time::{Duration, Instant};
+
+/// Send a state update, e.g. `STATUS=...`, to the service manager.  Nothing
+/// is sent if the process was not started by systemd.
+pub fn notify(state: &str) -> io::Result<()> {
+    let socket_path = match env::var_os("NOTIFY_SOCKET") {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    let socket = UnixDatagram::unbound()?;
+    let path = socket_path.as_bytes();
+    if path.starts_with(b"@") {
+        let addr = SocketAddr::from_abstract_name(&path[1..])?;
+        socket.send_to_addr(state.as_bytes(), &addr)?;
+    } else {
+        socket.send_to(state.as_bytes(), &socket_path)?;
+    }
+
+    Ok(())
+}
+
+/// The watchdog of the service manager, if it is enabled for this process.
+pub struct Watchdog {
+    /// Half the watchdog timeout, as recommended by `sd_watchdog_enabled(3)`.
+    interval: Option<Duration>,
+    last_ping: Instant,
+}
+
+impl Watchdog {
+    /// Read the watchdog timeout from `WATCHDOG_USEC`, which only applies to
+    /// this process if `WATCHDOG_PID` is unset or its PID.
+    pub fn new() -> Watchdog {
+        let for_us = env::var("WATCHDOG_PID")
+            .map(|pid| pid.parse() == Ok(process::id()))
+            .unwrap_or(true);
+        let interval = env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|usec| usec.parse::<u64>().ok())
+            .filter(|usec| for_us && *usec > 0)
+            .map(|usec| Duration::from_micros(usec / 2));
+        Watchdog {
+            interval,
+            last_ping: Instant::now(),
+        }
+    }
+
+    /// Return when the next ping is due, if the watchdog is enabled.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.interval.map(|interval| self.last_ping + interval)
+    }
+
+    /// Send `WATCHDOG=1` if the watchdog is enabled.
+    pub fn ping(&mut self) -> io::Result<()> {
+        if self.interval.is_none() {
+            return Ok(());
+        }
+        self.last_ping = Instant::now();
+        notify("WATCHDOG=1")
+    }
+}
@@ -0,0 +1,255 @@
+//! # TOML parser
+//!
+//! A parser for the subset of TOML used by the configuration file: comments,
+//! `key = value` pairs, `[table]` and `[[array]]` headers, and string,
+//! integer, float, boolean, and array values.
+
+/// A parsed TOML value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+/// A TOML table with its keys in the order they appear in the file.
+pub type Table = Vec<(String, Value)>;
+
+/// Parse a TOML document into its root table.  Errors are prefixed with the
+/// line number.
+pub fn parse(input: &str) -> Result<Table, String> {
+    let mut root: Table = vec![];
+    // The header of the table which key-value pairs are currently added to.
+    let mut current: Option<(String, bool)> = None;
+
+    let mut lines = input.lines().enumerate();
+    while let Some((idx, line)) = lines.next() {
+        let lineno = idx + 1;
+        let mut line = String::from(strip_comment(line).trim());
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with("[[") {
+            let name = header_name(&line, "[[", "]]").map_err(|e| at(lineno, e))?;
+            match lookup(&mut root, &name) {
+                Some(Value::Array(tables)) => tables.push(Value::Table(vec![])),
+                Some(_) => return Err(at(lineno, format!("{} is not an array of tables", name))),
+                None => root.push((name.clone(), Value::Array(vec![Value::Table(vec![])]))),
+            }
+            current = Some((name, true));
+            continue;
+        }
+
+        if line.starts_with('[') {
+            let name = header_name(&line, "[", "]").map_err(|e| at(lineno, e))?;
+            if lookup(&mut root, &name).is_some() {
+                return Err(at(lineno, format!("{} is defined twice", name)));
+            }
+            root.push((name.clone(), Value::Table(vec![])));
+            current = Some((name, false));
+            continue;
+        }
+
+        // Arrays may span several lines.
+        while !brackets_balanced(&line) {
+            match lines.next() {
+                Some((_, next)) => {
+                    line.push(' ');
+                    line.push_str(strip_comment(next).trim());
+                }
+                None => return Err(at(lineno, String::from("unterminated array"))),
+            }
+        }
+
+        let eq = line.find('=').ok_or_else(|| at(lineno, String::from("expected key = value")))?;
+        let key = unquote_key(line[..eq].trim()).map_err(|e| at(lineno, e))?;
+        let value = parse_value(line[eq + 1..].trim())
+            .map_err(|e| at(lineno, format!("{}: {}", key, e)))?;
+
+        let table = match current {
+            None => &mut root,
+            Some((ref name, is_array)) => match lookup(&mut root, name) {
+                Some(Value::Table(table)) if !is_array => table,
+                Some(Value::Array(tables)) if is_array => match tables.last_mut() {
+                    Some(Value::Table(table)) => table,
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            },
+        };
+
+        if table.iter().any(|(name, _)| *name == key) {
+            return Err(at(lineno, format!("{} is defined twice", key)));
+        }
+        table.push((key, value));
+    }
+
+    Ok(root)
+}
+
+/// Prefix an error message with the line number.
+fn at(lineno: usize, msg: String) -> String {
+    format!("line {}: {}", lineno, msg)
+}
+
+/// Find the value with the provided key in a table.
+fn lookup<'a>(table: &'a mut Table, key: &str) -> Option<&'a mut Value> {
+    table.iter_mut().find(|(name, _)| name == key).map(|(_, value)| value)
+}
+
+/// Call `f` with the index of every character outside of strings until it
+/// returns true and return that index.
+fn find_unquoted<F: FnMut(usize, char) -> bool>(s: &str, mut f: F) -> Option<usize> {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (idx, c) in s.char_indices() {
+        if in_string {
+            match c {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => in_string = false,
+                _ => escaped = false,
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if f(idx, c) {
+            return Some(idx);
+        }
+    }
+    None
+}
+
+/// Remove a trailing comment.
+fn strip_comment(line: &str) -> &str {
+    match find_unquoted(line, |_, c| c == '#') {
+        Some(idx) => &line[..idx],
+        None => line,
+    }
+}
+
+/// Return true if every `[` outside of strings has a matching `]`.
+fn brackets_balanced(line: &str) -> bool {
+    let mut depth = 0;
+    find_unquoted(line, |_, c| {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ => (),
+        }
+        false
+    });
+    depth <= 0
+}
+
+/// Extract the table name from a `[name]` or `[[name]]` header.
+fn header_name(line: &str, open: &str, close: &str) -> Result<String, String> {
+    if !line.ends_with(close) {
+        return Err(format!("expected {} at the end of the table header", close));
+    }
+    unquote_key(line[open.len()..line.len() - close.len()].trim())
+}
+
+/// Validate a bare key or remove the quotes from a quoted key.
+fn unquote_key(key: &str) -> Result<String, String> {
+    if key.starts_with('"') {
+        return parse_string(key);
+    }
+
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+    if key.is_empty() || !key.chars().all(valid) {
+        return Err(format!("invalid key {:?}", key));
+    }
+    Ok(String::from(key))
+}
+
+/// Parse a single value.
+fn parse_value(value: &str) -> Result<Value, String> {
+    match value {
+        "" => Err(String::from("missing value")),
+        "true" => Ok(Value::Boolean(true)),
+        "false" => Ok(Value::Boolean(false)),
+        _ if value.starts_with('"') => parse_string(value).map(Value::String),
+        _ if value.starts_with('[') => parse_array(value),
+        _ => {
+            let number = value.replace('_', "");
+            if let Ok(int) = number.parse() {
+                Ok(Value::Integer(int))
+            } else if let Ok(float) = number.parse() {
+                Ok(Value::Float(float))
+            } else {
+                Err(format!("invalid value {}", value))
+            }
+        }
+    }
+}
+
+/// Parse a basic string, i.e. one in double quotes, handling escapes.
+fn parse_string(value: &str) -> Result<String, String> {
+    let mut string = String::new();
+    let mut chars = value.chars().skip(1);
+    loop {
+        match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+                Some('n') => string.push('\n'),
+                Some('t') => string.push('\t'),
+                Some('"') => string.push('"'),
+                Some('\\') => string.push('\\'),
+                Some('u') => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    let c = u32::from_str_radix(&code, 16)
+                        .ok()
+                        .and_then(std::char::from_u32)
+                        .ok_or_else(|| format!("invalid escape \\u{}", code))?;
+                    string.push(c);
+                }
+                Some(c) => return Err(format!("invalid escape \\{}", c)),
+                None => return Err(String::from("unterminated string")),
+            },
+            Some(c) => string.push(c),
+            None => return Err(String::from("unterminated string")),
+        }
+    }
+
+    if chars.next().is_some() {
+        return Err(format!("unexpected characters after {}", value));
+    }
+    Ok(string)
+}
+
+/// Parse an array of values.
+fn parse_array(value: &str) -> Result<Value, String> {
+    if !value.ends_with(']') {
+        return Err(String::from("expected ] at the end of the array"));
+    }
+
+    let inner = &value[1..value.len() - 1];
+    let mut items = vec![];
+    let mut start = 0;
+    let mut depth = 0;
+    find_unquoted(inner, |idx, c| {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(&inner[start..idx]);
+                start = idx + 1;
+            }
+            _ => (),
+        }
+        false
+    });
+    items.push(&inner[start..]);
+
+    // A trailing comma is allowed.
+    let values: Result<Vec<Value>, String> = items
+        .iter()
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(parse_value)
+        .collect();
+    values.map(Value::Array)
+}
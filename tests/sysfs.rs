@@ -0,0 +1,459 @@
+//! Readouts of the fixture devices in `tests/fixtures`, which mimic the
+//! layout of sysfs and procfs.
+
+extern crate rwmstatus;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use rwmstatus::*;
+
+/// Serialises the tests which change or depend on the process-wide state of
+/// the library, i.e. the root of `/proc` and `/sys` and the cache of static
+/// attributes.
+static GLOBALS: Mutex<()> = Mutex::new(());
+
+/// Exclusive access to the process-wide state of the library.  `/proc` and
+/// `/sys` are read from `/` again once it is dropped, even if the test fails.
+struct Globals {
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Globals {
+    fn lock() -> Globals {
+        // The state of a failed test was restored when its guard dropped.
+        let lock = GLOBALS.lock().unwrap_or_else(|err| err.into_inner());
+        Globals { _lock: lock }
+    }
+
+    /// Read `/proc` and `/sys` from below the provided directory.
+    fn with_root(root: &Path) -> Globals {
+        let globals = Globals::lock();
+        set_root(root);
+        globals
+    }
+}
+
+impl Drop for Globals {
+    fn drop(&mut self) {
+        set_root(Path::new("/"));
+    }
+}
+
+/// Return the path of the provided fixture.
+fn fixture(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(path)
+}
+
+/// Create an empty directory for the files a test changes.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rwmstatus-{}{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn temp_labelled_and_unlabelled() {
+    let hwmon = fixture("hwmon/hwmon0");
+    assert_eq!(get_temp(&hwmon, TempUnit::Celsius).unwrap(), "Package id 0:45°C|47°C");
+    assert_eq!(get_temp(&hwmon, TempUnit::Fahrenheit).unwrap(), "Package id 0:113°F|118°F");
+
+    let temps = get_temp_raw(&hwmon).unwrap();
+    assert_eq!(temps.len(), 2);
+    assert_eq!(temps[0].name.as_deref(), Some("coretemp"));
+    assert_eq!(temps[1].label, None);
+    assert_eq!(temps[1].millidegrees, 47500.0);
+}
+
+#[test]
+fn temp_numeric_order() {
+    // temp10 comes after temp2 even though it sorts before it as a name.
+    let hwmon = fixture("hwmon/hwmon3");
+    assert_eq!(
+        get_temp(&hwmon, TempUnit::Celsius).unwrap(),
+        "Package id 0:50°C|51°C|Core 8:60°C"
+    );
+
+    let labels: Vec<Option<String>> =
+        get_temp_raw(&hwmon).unwrap().into_iter().map(|temp| temp.label).collect();
+    assert_eq!(labels, [Some(String::from("Package id 0")), None, Some(String::from("Core 8"))]);
+}
+
+#[test]
+fn temp_unit_conversion() {
+    let fahrenheit = |celsius: f64| TempUnit::Fahrenheit.degrees(celsius * 1000.0);
+    assert_eq!(fahrenheit(-40.0), -40);
+    assert_eq!(fahrenheit(0.0), 32);
+    assert_eq!(fahrenheit(37.0), 99);
+    assert_eq!(fahrenheit(-17.8), 0);
+    assert_eq!(fahrenheit(100.0), 212);
+
+    // Celsius is truncated towards zero.
+    assert_eq!(TempUnit::Celsius.degrees(47_900.0), 47);
+    assert_eq!(TempUnit::Celsius.degrees(-5_500.0), -5);
+}
+
+#[test]
+fn temp_malformed() {
+    let hwmon = fixture("hwmon/hwmon1");
+    match get_temp(&hwmon, TempUnit::Celsius) {
+        Err(StatusError::File(path, err)) => {
+            assert_eq!(path, hwmon.join("temp1_input"));
+            assert!(matches!(*err, StatusError::ParseFloat(_)));
+        }
+        result => panic!("unexpected {:?}", result),
+    }
+}
+
+#[test]
+fn temp_unreadable_input_is_skipped() {
+    // temp2_input is empty, as if the sensor had no data.
+    let hwmon = fixture("hwmon/hwmon4");
+    assert_eq!(get_temp(&hwmon, TempUnit::Celsius).unwrap(), "Composite:38°C|41°C");
+
+    let inputs: Vec<PathBuf> =
+        get_temp_raw(&hwmon).unwrap().into_iter().map(|temp| temp.input).collect();
+    assert_eq!(inputs, [hwmon.join("temp1_input"), hwmon.join("temp3_input")]);
+}
+
+#[test]
+fn temp_without_sensors() {
+    let result = get_temp(&fixture("hwmon/hwmon2"), TempUnit::Celsius);
+    assert!(matches!(result, Err(StatusError::NotPresent(_))));
+}
+
+#[test]
+fn temp_missing_device() {
+    let hwmon = fixture("hwmon/hwmon9");
+    match get_temp(&hwmon, TempUnit::Celsius) {
+        Err(StatusError::File(path, err)) => {
+            assert_eq!(path, hwmon);
+            assert!(matches!(*err, StatusError::Io(_)));
+        }
+        result => panic!("unexpected {:?}", result),
+    }
+}
+
+#[test]
+fn batt_energy() {
+    let batt = fixture("power_supply/BAT_energy");
+    assert_eq!(get_batt(&batt).unwrap(), "50%- 3:00 -10.0W");
+    assert_eq!(get_batt_energy(&batt).unwrap(), "30.0Wh-");
+    assert_eq!(get_batt_power(&batt).unwrap(), "-10.0W");
+
+    let readout = get_batt_raw(&batt).unwrap();
+    assert_eq!(readout.state, ChargeState::Discharging);
+    assert_eq!(readout.health.map(f64::round), Some(83.0));
+    assert_eq!(readout.cycles, None);
+}
+
+#[test]
+fn batt_charge() {
+    // The power is computed from the current and the voltage.
+    let batt = fixture("power_supply/BAT_charge");
+    assert_eq!(get_batt(&batt).unwrap(), "50%+ 2:00 +6.0W");
+    assert_eq!(get_batt_energy(&batt).unwrap(), "24.0Wh+");
+    assert_eq!(get_batt_power(&batt).unwrap(), "+6.0W");
+    assert_eq!(get_batt_raw(&batt).unwrap().health, Some(75.0));
+}
+
+#[test]
+fn batt_total_of_charge_and_energy() {
+    // The charge is converted to energy with the voltage before the
+    // capacities are added up.
+    let batts = [fixture("power_supply/BAT_energy"), fixture("power_supply/BAT_charge")];
+    assert_eq!(get_total_batt(&batts).unwrap(), "50%+");
+
+    // Only the charging battery counts towards the time and the power.
+    let readout = get_total_batt_raw(&batts).unwrap();
+    assert_eq!(readout.to_string(), "50%+ 5:20 +6.0W");
+    assert_eq!(readout.energy, Some(54.0));
+    assert_eq!(readout.health.map(f64::round), Some(80.0));
+}
+
+#[test]
+fn batt_malformed() {
+    let batt = fixture("power_supply/BAT_malformed");
+    match get_batt(&batt) {
+        Err(StatusError::File(path, err)) => {
+            assert_eq!(path, batt.join("charge_now"));
+            assert!(matches!(*err, StatusError::ParseNum(_)));
+        }
+        result => panic!("unexpected {:?}", result),
+    }
+}
+
+#[test]
+fn batt_absent() {
+    let result = get_batt(&fixture("power_supply/BAT_absent"));
+    assert!(matches!(result, Err(StatusError::NotPresent(ref name)) if name == "BAT_absent"));
+
+    let batts = [fixture("power_supply/BAT_absent"), fixture("power_supply/BAT_malformed")];
+    assert!(matches!(get_total_batt(&batts), Err(StatusError::NotPresent(_))));
+}
+
+#[test]
+fn batt_missing_device() {
+    let batt = fixture("power_supply/BAT9");
+    match get_batt(&batt) {
+        Err(StatusError::File(path, _)) => assert_eq!(path, batt.join("present")),
+        result => panic!("unexpected {:?}", result),
+    }
+}
+
+#[test]
+fn batt_static_attributes() {
+    // The design capacity is read once and the charge on every readout.
+    let _globals = Globals::lock();
+    let batt = scratch("BAT");
+    for name in &["present", "status", "charge_now", "charge_full", "charge_full_design"] {
+        fs::copy(fixture("power_supply/BAT_charge").join(name), batt.join(name)).unwrap();
+    }
+    assert_eq!(get_batt_raw(&batt).unwrap().percent, 50.0);
+
+    fs::write(batt.join("charge_full_design"), "2000000\n").unwrap();
+    fs::write(batt.join("charge_now"), "1000000\n").unwrap();
+    assert_eq!(get_batt_raw(&batt).unwrap().percent, 25.0);
+
+    forget_static_attributes();
+    assert_eq!(get_batt_raw(&batt).unwrap().percent, 50.0);
+
+    // Swapping a battery may not add or remove its device, so whether it is
+    // present is read every time.
+    fs::write(batt.join("present"), "0\n").unwrap();
+    assert!(matches!(get_batt_raw(&batt), Err(StatusError::NotPresent(_))));
+    fs::remove_dir_all(&batt).unwrap();
+}
+
+#[test]
+fn disk_missing_mount() {
+    let disk = get_disk_raw(&fixture("hwmon")).unwrap();
+    assert!(disk.available <= disk.total);
+
+    let result = get_disk_raw(&fixture("no/such/mount"));
+    assert!(matches!(result, Err(StatusError::System(-1))));
+}
+
+#[test]
+fn root_override() {
+    let _globals = Globals::with_root(&fixture("root"));
+
+    let mem = get_mem_raw().unwrap();
+    assert_eq!(mem.total, 16_000_000 * 1024);
+    assert_eq!(mem.used, 4_000_000 * 1024);
+    assert_eq!(mem.swap_used, 500_000 * 1024);
+    assert_eq!(mem.percent(), 25.0);
+    assert_eq!(get_mem().unwrap(), "3.8G/15.3G");
+
+    assert_eq!(get_cpu_times().unwrap(), (850, 1000));
+    assert_eq!(get_core_times().unwrap(), vec![(410, 500), (440, 500)]);
+    assert_eq!(get_net_bytes("eth0").unwrap(), (1234, 5678));
+    assert!(get_net_bytes("eth1").is_err());
+}
+
+#[test]
+fn builder_only_looks_for_the_provided_devices() {
+    let _globals = Globals::lock();
+    let rwmstatus = RwmStatusBuilder::new()
+        .with_hwmon_path(fixture("hwmon"))
+        .with_monitor(Box::new(LoadMonitor::new("load")))
+        .build();
+    assert!(rwmstatus.get_temperatures().is_some());
+    assert_eq!(rwmstatus.get_batteries(), None);
+    assert_eq!(rwmstatus.get_brightness(), None);
+    assert!(rwmstatus.get_monitor("load").is_some());
+}
+
+#[test]
+fn cpu_usage_between_two_samples() {
+    let root = scratch("cpu");
+    fs::create_dir(root.join("proc")).unwrap();
+    let _globals = Globals::with_root(&root);
+    let mut rwmstatus = RwmStatusBuilder::new().build();
+    rwmstatus.set_cpu_per_core(true);
+
+    // There is no usage until there is a previous sample.
+    fs::copy(fixture("root/proc/stat"), root.join("proc/stat")).unwrap();
+    assert_eq!(rwmstatus.get_cpu_usage(), "--% --%|--%");
+
+    fs::write(
+        root.join("proc/stat"),
+        "cpu  125 0 62 863 50 0 0 0 0 0\n\
+         cpu0 80 0 40 440 10 0 0 0 0 0\n\
+         cpu1 45 0 22 423 40 0 0 0 0 0\n",
+    )
+    .unwrap();
+    assert_eq!(rwmstatus.get_cpu_usage(), "37% 43%|23%");
+    assert_eq!(rwmstatus.get_value(&Section::Cpu).map(f64::round), Some(37.0));
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn wifi_link_quality() {
+    // The fixture interfaces have no ESSID, so only the link quality of 54
+    // out of 70 from /proc/net/wireless is shown.
+    let _globals = Globals::with_root(&fixture("root"));
+    assert_eq!(get_wifi("wlfix0").unwrap(), "77%");
+    let result = get_wifi("wlfix1");
+    assert!(matches!(result, Err(StatusError::NotPresent(ref name)) if name == "wlfix1"));
+    assert!(matches!(get_wifi("wlfix9"), Err(StatusError::NotPresent(_))));
+}
+
+/// Return a status over the fixture devices of `tests/fixtures/status`.
+fn fixture_status() -> RwmStatus {
+    RwmStatusBuilder::new()
+        .with_hwmon_path(fixture("status/hwmon"))
+        .with_batteries(fixture("status/power_supply"))
+        .build()
+}
+
+#[test]
+fn status_format_custom_order_and_separators() {
+    let _globals = Globals::with_root(&fixture("root"));
+    let mut rwmstatus = fixture_status();
+    rwmstatus.set_format(StatusFormat {
+        separator: String::from(" | "),
+        device_separator: String::from(","),
+        sections: vec![
+            (Section::Batteries, String::from("B:")),
+            (Section::Memory, String::from("M:")),
+            (Section::Temperatures, String::from("T:")),
+        ],
+        template: None,
+        markup: Markup::Plain,
+    });
+    assert_eq!(
+        rwmstatus.get_status(),
+        "B:50%- 3:00 -10.0W,50%+ 2:00 +6.0W | M:3.8G/15.3G | \
+         T:Package id 0:45°C,47°C,Package id 0:50°C,51°C,Core 8:60°C"
+    );
+}
+
+#[test]
+fn uptime_across_a_day() {
+    let root = scratch("uptime");
+    fs::create_dir(root.join("proc")).unwrap();
+    let _globals = Globals::with_root(&root);
+    let uptime = |contents: &str| {
+        fs::write(root.join("proc/uptime"), contents).unwrap();
+        get_uptime()
+    };
+
+    // The fractional seconds are truncated.
+    assert_eq!(uptime("86399.99 171234.56\n").unwrap(), "up 23:59");
+    assert_eq!(uptime("86400.00 171236.00\n").unwrap(), "up 1d 0:00");
+    assert_eq!(uptime("93784.70 185000.00\n").unwrap(), "up 1d 2:03");
+    assert!(matches!(uptime("up 171236.00\n"), Err(StatusError::ParseFloat(_))));
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn time_zones_with_their_own_formats() {
+    let _globals = Globals::lock();
+    let mut rwmstatus = RwmStatus::new(
+        &fixture("status/hwmon"),
+        &fixture("status/power_supply"),
+        &fixture("status/backlight"),
+        &[('U', "UTC", Some("%Z")), ('T', "Asia/Tokyo", Some("UTC%:z"))],
+    );
+    rwmstatus.set_time_format("local");
+    assert_eq!(rwmstatus.get_times(), "U:UTC T:UTC+09:00 local");
+}
+
+#[test]
+fn status_format_default() {
+    // New sections are only shown once they are listed.
+    let sections: Vec<Section> =
+        StatusFormat::default().sections.into_iter().map(|(section, _)| section).collect();
+    assert_eq!(
+        sections,
+        [Section::Temperatures, Section::LoadAvgs, Section::Batteries, Section::Times]
+    );
+}
+
+#[test]
+fn parallel_and_serial_readouts_match() {
+    // Both are read from a status of their own so that neither sees the
+    // samples the other took.
+    let _globals = Globals::with_root(&fixture("root"));
+    let format = StatusFormat {
+        sections: vec![
+            (Section::Temperatures, String::from("T:")),
+            (Section::Memory, String::from("M:")),
+            (Section::Batteries, String::from("B:")),
+            (Section::Times, String::new()),
+        ],
+        ..StatusFormat::default()
+    };
+    let status = || {
+        let mut rwmstatus = RwmStatusBuilder::new()
+            .with_hwmon_path(fixture("status/hwmon"))
+            .with_batteries(fixture("status/power_supply"))
+            .with_tz_format('U', "UTC", "%Z")
+            .with_tz_format('T', "Asia/Tokyo", "UTC%:z")
+            .build();
+        rwmstatus.set_time_format("local");
+        rwmstatus
+    };
+
+    let parallel = status().collect_all().format(&format);
+    assert_eq!(parallel, status().snapshot().format(&format));
+    assert_eq!(
+        parallel,
+        "T:Package id 0:45°C|47°C|Package id 0:50°C|51°C|Core 8:60°C M:3.8G/15.3G \
+         B:50%- 3:00 -10.0W|50%+ 2:00 +6.0W U:UTC T:UTC+09:00 local"
+    );
+}
+
+#[test]
+fn rescan_keeps_samples() {
+    let root = scratch("rescan");
+    fs::create_dir(root.join("proc")).unwrap();
+    fs::create_dir(root.join("power_supply")).unwrap();
+    let _globals = Globals::with_root(&root);
+    let mut rwmstatus = RwmStatusBuilder::new().with_batteries(root.join("power_supply")).build();
+    fs::copy(fixture("root/proc/stat"), root.join("proc/stat")).unwrap();
+    assert_eq!(rwmstatus.get_cpu_usage(), "--%");
+    assert_eq!(rwmstatus.get_batteries(), None);
+
+    let batt = root.join("power_supply/BAT0");
+    fs::create_dir(&batt).unwrap();
+    for name in &["present", "status", "energy_now", "energy_full", "energy_full_design"] {
+        fs::copy(fixture("power_supply/BAT_energy").join(name), batt.join(name)).unwrap();
+    }
+    rwmstatus.rescan();
+    assert_eq!(rwmstatus.get_batteries().as_deref(), Some("50%-"));
+
+    // The CPU usage is measured from the sample before the rescan.
+    fs::write(root.join("proc/stat"), "cpu  125 0 62 863 50 0 0 0 0 0\n").unwrap();
+    assert_eq!(rwmstatus.get_cpu_usage(), "37%");
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn alerts_are_raised_once() {
+    // BAT0 of the fixtures is discharging at 50%.
+    let _globals = Globals::with_root(&fixture("root"));
+    let status = || {
+        let mut rwmstatus = fixture_status();
+        rwmstatus.add_alert(Section::Batteries, Limit::Below(60.0), 5.0, "low: {batt}");
+        rwmstatus
+    };
+
+    let mut rwmstatus = status();
+    let snapshot = rwmstatus.snapshot();
+    assert_eq!(snapshot.values.get(&Section::Batteries), Some(50.0));
+    assert!(snapshot.values.discharging);
+    assert_eq!(rwmstatus.take_alerts(&snapshot), ["low: 50%- 3:00 -10.0W|50%+ 2:00 +6.0W"]);
+    assert!(rwmstatus.take_alerts(&snapshot).is_empty());
+
+    // Neither a rescan nor a status replacing this one raise it again.
+    rwmstatus.rescan();
+    assert!(rwmstatus.take_alerts(&snapshot).is_empty());
+    let reloaded = status();
+    reloaded.inherit_alerts(&rwmstatus);
+    assert!(reloaded.take_alerts(&snapshot).is_empty());
+    assert_eq!(status().take_alerts(&snapshot).len(), 1);
+}